@@ -18,7 +18,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Determine input: argument, file content, or default
     let input: String = select_input(parsed_args, default_hex);
     let key: Vec<u8> = string_to_bytes(input);
-    let padded: Vec<u8> = pkcs7_padding(&key, 20);
+    let padded: Vec<u8> = pkcs7_padding(&key, 20)?;
 
     println!(" Padded Bytes: {:?}", padded);
     println!("Padded String: {:?}", String::from_utf8_lossy(&padded));
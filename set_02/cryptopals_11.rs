@@ -5,7 +5,10 @@ Solution by: Abbas Moosajee
 Brief: [An ECB/CBC detection oracle] */
 
 use std::{env, error::Error};
-use cryptopals::{select_input, string_to_bytes, pkcs7_padding, encryption_oracle_random, detect_mode};
+use cryptopals::{
+    select_input, string_to_bytes, pkcs7_padding, encryption_oracle_random_with_rng, detect_mode,
+    random_bytes, seeded_rng, CipherMode,
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!("Set 02, Challenge 11: An ECB/CBC detection oracle");
@@ -17,13 +20,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     // Determine input: argument, file content, or default
     let input: String = select_input(parsed_args, plain_text);
 
+    // Optional second argument replays a previous run
+    let seed: u64 = match args.get(2) {
+        Some(s) => s.parse()?,
+        None => u64::from_be_bytes(random_bytes(8).try_into().unwrap()),
+    };
+    let mut rng = seeded_rng(seed);
+
     // Input with many repeating blocks → reveals ECB
     let base_bytes: Vec<u8> = string_to_bytes(input.to_string());
-    let plain_bytes: Vec<u8> = pkcs7_padding(&base_bytes, 64);
-    println!("Test Text: {}", input);
+    let plain_bytes: Vec<u8> = pkcs7_padding(&base_bytes, 64)?;
+    println!("Test Text: {} (seed {})", input, seed);
     for _ in 0..13 {
-        let (ciphertext, actual) = encryption_oracle_random(&plain_bytes);
-        let detected: &'static str = detect_mode(&ciphertext);
+        let (ciphertext, actual) = encryption_oracle_random_with_rng(&plain_bytes, &mut rng);
+        let detected: CipherMode = detect_mode(&ciphertext);
         let check_oracle: bool = actual == detected;
         println!("Validity = {} | Actual: {:<3}  Detected: {:<3}", check_oracle, actual, detected);
     }
@@ -6,7 +6,7 @@ Brief: [Byte-at-a-time ECB decryption (Simple)] */
 
 use std::{env, error::Error};
 use cryptopals::{
-    select_input, base64_to_bytes, encryption_oracle, confirm_ecb, find_block_size, find_next_byte
+    select_input, base64_to_bytes, OracleContext, confirm_ecb, find_block_size, find_next_byte
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -19,12 +19,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     let base64_inp: String = select_input(parsed_args, default_suffix);
     let suffix_bytes: Vec<u8> = base64_to_bytes(&base64_inp.to_string()).expect("Invalid base64");
 
+    // Oracle with a fresh random key: ECB(input || suffix)
+    let oracle_ctx: OracleContext = OracleContext::from_os_rng();
+    let encryption_oracle = |data: &[u8], suffix: Vec<u8>| oracle_ctx.encryption_oracle(data, &suffix);
+
     // Step 1: Detect the block size used by the cipher
     let block_size: usize = find_block_size(encryption_oracle, suffix_bytes.to_vec());
     println!("Detected block size: {}", block_size);
 
     // Step 2: Confirm that ECB mode is being used
-    confirm_ecb(encryption_oracle, suffix_bytes.to_vec(), block_size);
+    confirm_ecb(encryption_oracle, suffix_bytes.to_vec(), block_size)?;
     println!("Confirmed ECB mode");
 
     // Step 3: Get expected result for verification
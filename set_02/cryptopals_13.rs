@@ -5,58 +5,35 @@ Solution by: Abbas Moosajee
 Brief: [ECB cut-and-paste]
 */
 
-use std::collections::HashMap;
 use std::error::Error;
 
-use cryptopals::{gen_key, pkcs7_padding, pkcs7_unpadding, AesStandard};
+use cryptopals::{gen_key, SecretKey, pkcs7_padding, pkcs7_unpadding, AesStandard, CutAndPaste,
+    Cookie, CookieCodec, CookieFormat, Escaping};
 
-// --- helper: sanitize email ---
-fn sanitize(input: &[u8]) -> Vec<u8> {
-    input.iter().copied().filter(|&b| b != b'&' && b != b'=').collect()
+// --- profile_for ---
+// '&' and '=' are stripped from the email, as the challenge asks; every
+// other byte goes through unchanged
+fn profile_for(email: &[u8], uid: &str, role: &str) -> Vec<u8> {
+    let profile = Cookie::new()
+        .with("email", email)
+        .with("uid", uid)
+        .with("role", role);
+    profile_codec().encode(&profile)
 }
 
-// --- helper: encode key-value pairs ---
-fn encode_profile(fields: &[(&[u8], &[u8])]) -> Vec<u8> {
-    let mut out = Vec::new();
-    for (i, (k, v)) in fields.iter().enumerate() {
-        out.extend_from_slice(k);
-        out.push(b'=');
-        out.extend_from_slice(v);
-        if i != fields.len() - 1 {
-            out.push(b'&');
-        }
-    }
-    out
+fn profile_codec() -> CookieCodec {
+    CookieCodec::new(CookieFormat::Ampersand).with_escaping(Escaping::Strip)
 }
 
-// --- helper: parse key-value pairs ---
-fn build_cookie_object(input: &[u8]) -> HashMap<String, String> {
-    let s = String::from_utf8_lossy(input);
-    let mut map = HashMap::new();
-    for pair in s.split('&') {
-        let mut parts = pair.splitn(2, '=');
-        let k = parts.next().unwrap_or("").to_string();
-        let v = parts.next().unwrap_or("").to_string();
-        map.insert(k, v);
-    }
-    map
-}
-
-// --- profile_for ---
-fn profile_for(email: &[u8], uid: &[u8], role: &[u8]) -> Vec<u8> {
-    let clean_email = sanitize(email);
-    let fields = [
-        (b"email".as_ref(), clean_email.as_slice()),
-        (b"uid".as_ref(), uid),
-        (b"role".as_ref(), role),
-    ];
-    encode_profile(&fields)
+// pairs with printable values
+fn readable(cookie: &Cookie) -> Vec<(&str, String)> {
+    cookie.pairs().iter().map(|(k, v)| (k.as_str(), String::from_utf8_lossy(v).into_owned())).collect()
 }
 
 // --- encryption ---
 fn encrypt_profile(email: &[u8], rand_key: &[u8]) -> Vec<u8> {
-    let encoded_profile: Vec<u8> = profile_for(email, b"10", b"user");
-    let padded_profile: Vec<u8> = pkcs7_padding(&encoded_profile, 16);
+    let encoded_profile: Vec<u8> = profile_for(email, "10", "user");
+    let padded_profile: Vec<u8> = pkcs7_padding(&encoded_profile, 16).unwrap();
 
     let cipher = AesStandard::new(rand_key).unwrap();
     cipher.encrypt_ecb(&padded_profile).unwrap()
@@ -71,36 +48,23 @@ fn decrypt_profile(ciphertext: &[u8], rand_key: &[u8]) -> Vec<u8> {
 
 
 // --- perform the ECB cut-and-paste attack ---
-fn do_evil(rand_key: &[u8]) -> Vec<u8> {
-    // Blocksize = 16
-    // We want to make "admin" its own block with correct PKCS#7 padding
-
-    let normal = encrypt_profile(b"foooo@bar.com", rand_key);
-
-
-    let mut crafted = b"foooo@bar.".to_vec();
-    let mut admin_block = b"admin".to_vec();
-    // pad admin to 16
-    let pad_len = 16 - (admin_block.len() % 16);
-    admin_block.extend(std::iter::repeat(pad_len as u8).take(pad_len));
-    crafted.extend_from_slice(&admin_block);
-    crafted.extend_from_slice(b"com");
-
-    let crafted_ct = encrypt_profile(&crafted, rand_key);
-
-    // Step 3: forge ciphertext
-    // First two blocks (email+uid) from normal, then 'admin' block from crafted
-    let mut forged = Vec::new();
-    forged.extend_from_slice(&normal[0..32]);        // first two blocks
-    forged.extend_from_slice(&crafted_ct[16..32]);   // 'admin' block
-    forged
+fn do_evil(rand_key: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    // The planner aligns "role=" to a block boundary, encrypts a block-aligned
+    // "admin" + PKCS#7 padding, and splices the two ciphertexts together
+    let planner = CutAndPaste::new(b"email={}&uid=10&role=user", b"{}")?;
+    let (forged, plan) = planner.forge(|email: &[u8]| encrypt_profile(email, rand_key), b"role", b"admin")?;
+    println!(
+        "Block size {}: kept {} blocks of the aligned profile, pasted {} 'admin' block(s)",
+        plan.block_size, plan.keep_blocks, plan.value_blocks,
+    );
+    Ok(forged)
 }
 
 // --- main demo ---
 fn main() -> Result<(), Box<dyn Error>> {
     println!("Set 02, Challenge 13: ECB cut-and-paste");
 
-    let rand_key: Vec<u8> = gen_key(16);
+    let rand_key: SecretKey = gen_key(16);
 
     // Normal encode/decode
     let test_email = b"foo@bar.com";
@@ -108,15 +72,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let decrypted = decrypt_profile(&encrypted, &rand_key);
     println!("Decrypted normal: {}", String::from_utf8_lossy(&decrypted));
 
-    let parsed = build_cookie_object(&decrypted);
-    println!("Parsed normal: {:?}", parsed);
+    let parsed = profile_codec().parse(&decrypted)?;
+    println!("Parsed normal: {:?}", readable(&parsed));
 
     // Do the attack
-    let evil_ct = do_evil(&rand_key);
+    let evil_ct = do_evil(&rand_key)?;
     let evil_pt = decrypt_profile(&evil_ct, &rand_key);
     println!("Decrypted forged: {}", String::from_utf8_lossy(&evil_pt));
-    let parsed_evil = build_cookie_object(&evil_pt);
-    println!("Parsed forged: {:?}", parsed_evil);
+    let parsed_evil = profile_codec().parse_strict(&evil_pt)?;
+    println!("Parsed forged: {:?}, role = {:?}", readable(&parsed_evil), parsed_evil.get_str("role"));
 
     Ok(())
 }
@@ -8,7 +8,7 @@ use std::{env, error::Error};
 
 use cryptopals::{
     select_input, base64_to_bytes, confirm_ecb,
-    encryption_oracle, find_block_size, find_prefix_len
+    OracleContext, find_block_size, find_prefix_len
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -21,12 +21,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     let base64_inp: String = select_input(parsed_args, default_suffix);
     let suffix_bytes: Vec<u8> = base64_to_bytes(&base64_inp.to_string()).expect("Invalid base64");
 
+    // Oracle with a fresh random key and random prefix: ECB(prefix || input || suffix)
+    let oracle_ctx: OracleContext = OracleContext::from_os_rng();
+    let encryption_oracle = |data: &[u8], suffix: Vec<u8>| oracle_ctx.prefix_oracle(data, &suffix);
+
     // Step 1: Detect the block size used by the cipher
     let block_size: usize = find_block_size(encryption_oracle, suffix_bytes.clone());
     println!("Detected block size: {}", block_size);
 
     // Step 2: Confirm that ECB mode is being used
-    confirm_ecb(encryption_oracle, suffix_bytes.clone(), block_size);
+    confirm_ecb(encryption_oracle, suffix_bytes.clone(), block_size)?;
     println!("Confirmed ECB mode");
 
     // Step 3: Detect random prefix length
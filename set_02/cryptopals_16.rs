@@ -4,23 +4,19 @@ Puzzle Link: https://cryptopals.com/sets/2/challenges/16
 Solution by: Abbas Moosajee
 Brief: [CBC bitflipping attacks] */
 
-use cryptopals::{pkcs7_unpadding, AesStandard, pkcs7_padding, gen_key};
+use cryptopals::{pkcs7_unpadding, AesStandard, pkcs7_padding, gen_key, Cookie, CookieCodec, CookieFormat};
 
 /// Build and encrypt param string (mimics Python version)
 fn encrypt_params(userdata: &str, enc_key: &[u8], iv_box: &[u8; 16]) -> Vec<u8> {
-    // sanitize userdata
-    let safe: String = userdata.replace(';', "%3B").replace('=', "%3D");
-
-    let prefix: &'static [u8; 32] = b"comment1=cooking%20MCs;userdata=";
-    let suffix: &'static [u8; 42] = b";comment2=%20like%20a%20pound%20of%20bacon";
-
-    let mut params: Vec<u8> = Vec::new();
-    params.extend_from_slice(prefix);
-    params.extend_from_slice(safe.as_bytes());
-    params.extend_from_slice(suffix);
+    // the codec percent-escapes ';' and '=' (and spaces) in every value
+    let cookie = Cookie::new()
+        .with("comment1", "cooking MCs")
+        .with("userdata", userdata)
+        .with("comment2", " like a pound of bacon");
+    let params: Vec<u8> = params_codec().encode(&cookie);
 
     // pad and encrypt with your AesStandard::encrypt_cbc
-    let padded: Vec<u8> = pkcs7_padding(&params, 16);
+    let padded: Vec<u8> = pkcs7_padding(&params, 16).expect("16 is a valid block size");
 
     let aes: AesStandard = AesStandard::new(enc_key).expect("Failed to create AES");
     aes.encrypt_cbc(&padded, iv_box).expect("encrypt_cbc failed")
@@ -31,12 +27,25 @@ fn decrypt_params_and_check_admin(ciphertext: &[u8], enc_key:&[u8], iv_box: &[u8
     let aes: AesStandard = AesStandard::new(enc_key).expect("Failed to create AES");
     let decrypted: Vec<u8> = aes.decrypt_cbc(ciphertext, iv_box).expect("decrypt_cbc failed");
 
-    // unpad (returns Result<Vec<u8>, &str>)
-    match pkcs7_unpadding(&decrypted) {
-        Ok(plain) => plain.windows(b";admin=true;".len()).any(|w| w == b";admin=true;"),
+    // unpad (returns Result<Vec<u8>, PaddingError>)
+    let plain: Vec<u8> = match pkcs7_unpadding(&decrypted) {
+        Ok(plain) => plain,
+        Err(_) => return false,
+    };
+
+    // the service parses forgivingly; a strict parser sees the tampering
+    if let Err(e) = params_codec().parse_strict(&plain) {
+        println!("Strict parser rejects it: {}", e);
+    }
+    match params_codec().parse(&plain) {
+        Ok(cookie) => cookie.get("admin") == Some(&b"true"[..]),
         Err(_) => false,
     }
 }
+
+fn params_codec() -> CookieCodec {
+    CookieCodec::new(CookieFormat::Semicolon)
+}
 fn main() {
     println!("Set 02, Challenge 16: CBC bitflipping attack");
     let rand_key = gen_key(16);
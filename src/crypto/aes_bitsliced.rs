@@ -0,0 +1,297 @@
+use zeroize::Zeroize;
+
+/// Bitsliced AES state: eight 16-byte blocks processed together.
+///
+/// Plane `k` holds bit `k` of every byte; lane `16 * block + pos` of a plane is
+/// byte `pos` of block `block`. Every transformation is a fixed sequence of
+/// AND/XOR/shift operations, so nothing indexes memory or branches on data.
+pub type Planes = [u128; 8];
+
+pub const PARALLEL_BLOCKS: usize = 8;
+
+// bit 0 of every 16-bit block group
+const LANE_REP: u128 = 0x0001_0001_0001_0001_0001_0001_0001_0001;
+
+// ShiftRows: new byte `pos` comes from old byte SHIFT_ROWS[pos]
+const SHIFT_ROWS: [usize; 16] = [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11];
+const INV_SHIFT_ROWS: [usize; 16] = [0, 13, 10, 7, 4, 1, 14, 11, 8, 5, 2, 15, 12, 9, 6, 3];
+// rotate each column up by one row: new (c, r) = old (c, r + 1)
+const ROTATE_COLUMN: [usize; 16] = [1, 2, 3, 0, 5, 6, 7, 4, 9, 10, 11, 8, 13, 14, 15, 12];
+
+/// Round keys broadcast to all eight block lanes.
+#[derive(Clone)]
+pub struct BitslicedKeys {
+    round_keys: Vec<Planes>,
+}
+
+impl BitslicedKeys {
+    pub fn new(expanded_key: &[u8], rounds: usize) -> Self {
+        let round_keys = (0..=rounds)
+            .map(|round| {
+                let rk = &expanded_key[round * 16..round * 16 + 16];
+                let mut planes: Planes = [0; 8];
+                for (k, plane) in planes.iter_mut().enumerate() {
+                    let mut pattern: u128 = 0;
+                    for (pos, &b) in rk.iter().enumerate() {
+                        pattern |= (((b >> k) & 1) as u128) << pos;
+                    }
+                    // replicate the 16-lane pattern into every block group
+                    *plane = pattern * LANE_REP;
+                }
+                planes
+            })
+            .collect();
+        Self { round_keys }
+    }
+}
+
+impl Drop for BitslicedKeys {
+    fn drop(&mut self) {
+        self.round_keys.zeroize();
+    }
+}
+
+// ----------------------------
+// Packing
+// ----------------------------
+// Eight bytes are handled per u64 with multiply tricks instead of bit loops:
+// gathering bit k of each byte into one byte, and spreading a byte back out.
+const BYTE_LSBS: u64 = 0x0101_0101_0101_0101;
+const GATHER: u64 = 0x0102_0408_1020_4080;
+const DIAGONAL: u64 = 0x8040_2010_0804_0201;
+
+pub fn pack(blocks: &[[u8; 16]; PARALLEL_BLOCKS]) -> Planes {
+    let mut planes: Planes = [0; 8];
+    let bytes: Vec<u8> = blocks.concat();
+    for (q, chunk) in bytes.chunks_exact(8).enumerate() {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        for (k, plane) in planes.iter_mut().enumerate() {
+            // byte i's bit k lands on bit i of the top byte
+            let gathered = (((word >> k) & BYTE_LSBS).wrapping_mul(GATHER)) >> 56;
+            *plane |= (gathered as u128) << (8 * q);
+        }
+    }
+    planes
+}
+
+pub fn unpack(planes: &Planes) -> [[u8; 16]; PARALLEL_BLOCKS] {
+    let mut bytes = [0u8; 16 * PARALLEL_BLOCKS];
+    for (q, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+        let mut word: u64 = 0;
+        for (k, plane) in planes.iter().enumerate() {
+            let group = ((plane >> (8 * q)) & 0xff) as u64;
+            // copy the group into every byte, keep bit i in byte i, then squash to 0/1
+            let picked = group.wrapping_mul(BYTE_LSBS) & DIAGONAL;
+            let spread = ((picked + 0x7f7f_7f7f_7f7f_7f7f) >> 7) & BYTE_LSBS;
+            word |= spread << k;
+        }
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+
+    let mut blocks = [[0u8; 16]; PARALLEL_BLOCKS];
+    for (block, chunk) in blocks.iter_mut().zip(bytes.chunks_exact(16)) {
+        block.copy_from_slice(chunk);
+    }
+    blocks
+}
+
+// A lane permutation as (shift, mask) groups: every lane that moves by the same
+// distance is handled by a single shift. Built at compile time from the tables above.
+const fn group_count(perm: &[usize; 16]) -> usize {
+    let mut count = 0;
+    let mut d: isize = -15;
+    while d <= 15 {
+        let mut pos = 0;
+        while pos < 16 {
+            if perm[pos] as isize - pos as isize == d {
+                count += 1;
+                break;
+            }
+            pos += 1;
+        }
+        d += 1;
+    }
+    count
+}
+
+const fn shift_groups<const N: usize>(perm: &[usize; 16]) -> [(isize, u128); N] {
+    let mut groups = [(0isize, 0u128); N];
+    let mut n = 0;
+    let mut d: isize = -15;
+    while d <= 15 {
+        let mut pattern: u128 = 0;
+        let mut pos = 0;
+        while pos < 16 {
+            if perm[pos] as isize - pos as isize == d {
+                pattern |= 1 << pos;
+            }
+            pos += 1;
+        }
+        if pattern != 0 {
+            groups[n] = (d, pattern * LANE_REP);
+            n += 1;
+        }
+        d += 1;
+    }
+    groups
+}
+
+const SHIFT_ROWS_GROUPS: [(isize, u128); group_count(&SHIFT_ROWS)] = shift_groups(&SHIFT_ROWS);
+const INV_SHIFT_ROWS_GROUPS: [(isize, u128); group_count(&INV_SHIFT_ROWS)] = shift_groups(&INV_SHIFT_ROWS);
+const ROTATE_COLUMN_GROUPS: [(isize, u128); group_count(&ROTATE_COLUMN)] = shift_groups(&ROTATE_COLUMN);
+
+// Move byte positions within every block: new lane `pos` takes old lane `perm[pos]`
+fn permute_lanes(x: u128, groups: &[(isize, u128)]) -> u128 {
+    let mut out: u128 = 0;
+    for &(d, mask) in groups {
+        let moved = if d >= 0 { x >> d } else { x << -d };
+        out |= moved & mask;
+    }
+    out
+}
+
+fn permute(s: &Planes, groups: &[(isize, u128)]) -> Planes {
+    let mut out: Planes = [0; 8];
+    for k in 0..8 {
+        out[k] = permute_lanes(s[k], groups);
+    }
+    out
+}
+
+fn xor(a: &Planes, b: &Planes) -> Planes {
+    let mut out: Planes = [0; 8];
+    for k in 0..8 {
+        out[k] = a[k] ^ b[k];
+    }
+    out
+}
+
+// ----------------------------
+// GF(2^8) arithmetic on planes
+// ----------------------------
+fn xtime(a: &Planes) -> Planes {
+    // multiply by x, reducing with 0x1b (bits 0, 1, 3, 4)
+    [a[7], a[0] ^ a[7], a[1], a[2] ^ a[7], a[3] ^ a[7], a[4], a[5], a[6]]
+}
+
+fn gf_mul(a: &Planes, b: &Planes) -> Planes {
+    let mut prod = [0u128; 15];
+    for i in 0..8 {
+        for j in 0..8 {
+            prod[i + j] ^= a[i] & b[j];
+        }
+    }
+    reduce(prod)
+}
+
+// Squaring is linear in characteristic 2: bit i moves to bit 2i
+fn gf_square(a: &Planes) -> Planes {
+    let mut prod = [0u128; 15];
+    for i in 0..8 {
+        prod[2 * i] = a[i];
+    }
+    reduce(prod)
+}
+
+fn reduce(mut prod: [u128; 15]) -> Planes {
+    // x^8 = x^4 + x^3 + x + 1; reduce from the top so carries are folded again
+    for k in (8..15).rev() {
+        let hi = prod[k];
+        prod[k - 8] ^= hi;
+        prod[k - 7] ^= hi;
+        prod[k - 5] ^= hi;
+        prod[k - 4] ^= hi;
+    }
+    let mut out: Planes = [0; 8];
+    out.copy_from_slice(&prod[..8]);
+    out
+}
+
+// a^254 = a^-1 (and 0 -> 0), addition chain 2, 3, 12, 15, 240, 252, 254
+fn gf_inverse(a: &Planes) -> Planes {
+    let a2 = gf_square(a);
+    let a3 = gf_mul(&a2, a);
+    let a12 = gf_square(&gf_square(&a3));
+    let a15 = gf_mul(&a12, &a3);
+    let mut a240 = a15;
+    for _ in 0..4 {
+        a240 = gf_square(&a240);
+    }
+    let a252 = gf_mul(&a240, &a12);
+    gf_mul(&a252, &a2)
+}
+
+// ----------------------------
+// AES transformations
+// ----------------------------
+fn sub_bytes(s: &Planes) -> Planes {
+    let x = gf_inverse(s);
+    let mut out: Planes = [0; 8];
+    for i in 0..8 {
+        out[i] = x[i] ^ x[(i + 4) % 8] ^ x[(i + 5) % 8] ^ x[(i + 6) % 8] ^ x[(i + 7) % 8];
+        // affine constant 0x63
+        if (0x63 >> i) & 1 == 1 {
+            out[i] = !out[i];
+        }
+    }
+    out
+}
+
+fn inv_sub_bytes(s: &Planes) -> Planes {
+    let mut x: Planes = [0; 8];
+    for i in 0..8 {
+        x[i] = s[(i + 2) % 8] ^ s[(i + 5) % 8] ^ s[(i + 7) % 8];
+        // inverse affine constant 0x05
+        if (0x05 >> i) & 1 == 1 {
+            x[i] = !x[i];
+        }
+    }
+    gf_inverse(&x)
+}
+
+fn mix_columns(s: &Planes) -> Planes {
+    // out_r = 2(a_r ^ a_r+1) ^ a_r+1 ^ a_r+2 ^ a_r+3
+    let r1 = permute(s, &ROTATE_COLUMN_GROUPS);
+    let r2 = permute(&r1, &ROTATE_COLUMN_GROUPS);
+    let r3 = permute(&r2, &ROTATE_COLUMN_GROUPS);
+    xor(&xor(&xtime(&xor(s, &r1)), &r1), &xor(&r2, &r3))
+}
+
+fn inv_mix_columns(s: &Planes) -> Planes {
+    // InvMixColumns = MixColumns after a_r ^= 4(a_r ^ a_r+2)
+    let r2 = permute(&permute(s, &ROTATE_COLUMN_GROUPS), &ROTATE_COLUMN_GROUPS);
+    let w = xtime(&xtime(&xor(s, &r2)));
+    mix_columns(&xor(s, &w))
+}
+
+/// Encrypt eight blocks at once.
+pub fn encrypt_blocks(keys: &BitslicedKeys, rounds: usize, blocks: &[[u8; 16]; PARALLEL_BLOCKS]) -> [[u8; 16]; PARALLEL_BLOCKS] {
+    let rk = &keys.round_keys;
+    let mut s = xor(&pack(blocks), &rk[0]);
+    for round_key in &rk[1..rounds] {
+        s = sub_bytes(&s);
+        s = permute(&s, &SHIFT_ROWS_GROUPS);
+        s = mix_columns(&s);
+        s = xor(&s, round_key);
+    }
+    s = sub_bytes(&s);
+    s = permute(&s, &SHIFT_ROWS_GROUPS);
+    s = xor(&s, &rk[rounds]);
+    unpack(&s)
+}
+
+/// Decrypt eight blocks at once.
+pub fn decrypt_blocks(keys: &BitslicedKeys, rounds: usize, blocks: &[[u8; 16]; PARALLEL_BLOCKS]) -> [[u8; 16]; PARALLEL_BLOCKS] {
+    let rk = &keys.round_keys;
+    let mut s = xor(&pack(blocks), &rk[rounds]);
+    for round_key in rk[1..rounds].iter().rev() {
+        s = permute(&s, &INV_SHIFT_ROWS_GROUPS);
+        s = inv_sub_bytes(&s);
+        s = xor(&s, round_key);
+        s = inv_mix_columns(&s);
+    }
+    s = permute(&s, &INV_SHIFT_ROWS_GROUPS);
+    s = inv_sub_bytes(&s);
+    s = xor(&s, &rk[0]);
+    unpack(&s)
+}
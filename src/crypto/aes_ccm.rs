@@ -0,0 +1,155 @@
+use std::error::Error;
+use crate::crypto::aes_encyption::{AesStandard, AesError};
+use crate::basics::encodings::hex_to_bytes;
+
+// Valid CCM tag lengths M (RFC 3610, section 2)
+const CCM_TAG_LENGTHS: [usize; 7] = [4, 6, 8, 10, 12, 14, 16];
+
+impl AesStandard {
+    /// AES-CCM authenticated encryption (RFC 3610 / NIST SP 800-38C).
+    ///
+    /// The length field size L is set by the nonce: a nonce of 15 - L bytes,
+    /// so 7..=13 bytes for L = 8..=2. `tag_len` is one of 4, 6, ..., 16.
+    /// Returns the ciphertext and the tag.
+    pub fn encrypt_ccm(&self, plaintext: &[u8], aad: &[u8], nonce: &[u8], tag_len: usize) -> Result<(Vec<u8>, Vec<u8>), AesError> {
+        Self::ccm_check(plaintext.len(), nonce, tag_len)?;
+        let mac = self.ccm_mac(plaintext, aad, nonce, tag_len);
+        let (keystream_tag, ciphertext) = self.ccm_ctr(plaintext, nonce);
+        let tag: Vec<u8> = mac.iter().zip(keystream_tag.iter()).take(tag_len).map(|(t, s)| t ^ s).collect();
+        Ok((ciphertext, tag))
+    }
+
+    /// AES-CCM authenticated decryption; the tag length is `tag.len()`.
+    /// Fails with `AuthenticationFailed` on a bad tag.
+    pub fn decrypt_ccm(&self, ciphertext: &[u8], aad: &[u8], nonce: &[u8], tag: &[u8]) -> Result<Vec<u8>, AesError> {
+        Self::ccm_check(ciphertext.len(), nonce, tag.len())?;
+        let (keystream_tag, plaintext) = self.ccm_ctr(ciphertext, nonce);
+        let mac = self.ccm_mac(&plaintext, aad, nonce, tag.len());
+
+        // compare every byte so the check does not stop early
+        let diff = mac
+            .iter()
+            .zip(keystream_tag.iter())
+            .zip(tag.iter())
+            .fold(0u8, |acc, ((t, s), given)| acc | (t ^ s ^ given));
+        if diff != 0 {
+            return Err(AesError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    fn ccm_check(message_len: usize, nonce: &[u8], tag_len: usize) -> Result<(), AesError> {
+        if !(7..=13).contains(&nonce.len()) {
+            return Err(AesError::InvalidLength("CCM nonce must be 7 to 13 bytes"));
+        }
+        if !CCM_TAG_LENGTHS.contains(&tag_len) {
+            return Err(AesError::InvalidLength("CCM tag must be 4, 6, 8, 10, 12, 14 or 16 bytes"));
+        }
+        let length_len = 15 - nonce.len();
+        if length_len < 8 && (message_len as u64) >> (8 * length_len) != 0 {
+            return Err(AesError::InvalidLength("Message too long for the CCM length field"));
+        }
+        Ok(())
+    }
+
+    // CBC-MAC over B_0 || encoded AAD || plaintext, each part zero-padded to a block
+    fn ccm_mac(&self, plaintext: &[u8], aad: &[u8], nonce: &[u8], tag_len: usize) -> [u8; 16] {
+        let length_len = 15 - nonce.len();
+        let mut b0 = [0u8; 16];
+        b0[0] = (u8::from(!aad.is_empty()) << 6) | (((tag_len as u8 - 2) / 2) << 3) | (length_len as u8 - 1);
+        b0[1..1 + nonce.len()].copy_from_slice(nonce);
+        b0[1 + nonce.len()..].copy_from_slice(&(plaintext.len() as u64).to_be_bytes()[8 - length_len..]);
+
+        let mut input: Vec<u8> = b0.to_vec();
+        if !aad.is_empty() {
+            // AAD length prefix: 2 bytes below 2^16 - 2^8, else 0xfffe + 4 or 0xffff + 8
+            let a = aad.len() as u64;
+            if a < 0xff00 {
+                input.extend_from_slice(&(a as u16).to_be_bytes());
+            } else if a <= u32::MAX as u64 {
+                input.extend_from_slice(&[0xff, 0xfe]);
+                input.extend_from_slice(&(a as u32).to_be_bytes());
+            } else {
+                input.extend_from_slice(&[0xff, 0xff]);
+                input.extend_from_slice(&a.to_be_bytes());
+            }
+            input.extend_from_slice(aad);
+            input.resize(input.len().div_ceil(16) * 16, 0);
+        }
+        input.extend_from_slice(plaintext);
+        input.resize(input.len().div_ceil(16) * 16, 0);
+
+        let mut mac = [0u8; 16];
+        for block in input.chunks_exact(16) {
+            for (m, b) in mac.iter_mut().zip(block.iter()) {
+                *m ^= b;
+            }
+            mac = self.encrypt_block(&mac);
+        }
+        mac
+    }
+
+    // CTR with A_i = flags || nonce || i: returns S_0 (tag mask) and the data
+    // XORed with S_1, S_2, ...
+    fn ccm_ctr(&self, data: &[u8], nonce: &[u8]) -> ([u8; 16], Vec<u8>) {
+        let mut a0 = [0u8; 16];
+        a0[0] = (15 - nonce.len()) as u8 - 1;
+        a0[1..1 + nonce.len()].copy_from_slice(nonce);
+        let s0 = self.encrypt_block(&a0);
+
+        let mut a1 = a0;
+        a1[15] = 1;
+        // the counter never carries into the nonce: the length check keeps
+        // the block count below 2^(8L)
+        (s0, self.encrypt_ctr(data, &a1))
+    }
+}
+
+/// RFC 3610 packet vector #1 and NIST SP 800-38C examples 1 and 2.
+pub fn _test_ccm() -> Result<(), Box<dyn Error>> {
+    // (name, key, nonce, aad, plaintext, ciphertext || tag, tag length)
+    let vectors = [
+        (
+            "RFC 3610 #1",
+            "c0c1c2c3c4c5c6c7c8c9cacbcccdcecf",
+            "00000003020100a0a1a2a3a4a5",
+            "0001020304050607",
+            "08090a0b0c0d0e0f101112131415161718191a1b1c1d1e",
+            "588c979a61c663d2f066d0c2c0f989806d5f6b61dac38417e8d12cfdf926e0",
+            8,
+        ),
+        (
+            "SP 800-38C C.1",
+            "404142434445464748494a4b4c4d4e4f",
+            "10111213141516",
+            "0001020304050607",
+            "20212223",
+            "7162015b4dac255d",
+            4,
+        ),
+        (
+            "SP 800-38C C.2",
+            "404142434445464748494a4b4c4d4e4f",
+            "1011121314151617",
+            "000102030405060708090a0b0c0d0e0f",
+            "202122232425262728292a2b2c2d2e2f",
+            "d2a1f0e051ea5f62081a7792073d593d1fc64fbfaccd",
+            6,
+        ),
+    ];
+    for (name, key, nonce, aad, pt, expected, tag_len) in vectors {
+        let aes = AesStandard::new(&hex_to_bytes(key)?)?;
+        let (nonce, aad, pt) = (hex_to_bytes(nonce)?, hex_to_bytes(aad)?, hex_to_bytes(pt)?);
+        let (ct, tag) = aes.encrypt_ccm(&pt, &aad, &nonce, tag_len)?;
+        let joined: Vec<u8> = [ct.as_slice(), tag.as_slice()].concat();
+        let ok = joined == hex_to_bytes(expected)? && aes.decrypt_ccm(&ct, &aad, &nonce, &tag)? == pt;
+        println!("CCM {}: {}", name, if ok { "matches" } else { "mismatch!" });
+
+        let mut tampered = tag.clone();
+        tampered[0] ^= 1;
+        if !matches!(aes.decrypt_ccm(&ct, &aad, &nonce, &tampered), Err(AesError::AuthenticationFailed)) {
+            println!("CCM {}: accepted a tampered tag!", name);
+        }
+    }
+    Ok(())
+}
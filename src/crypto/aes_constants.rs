@@ -1,6 +1,6 @@
 pub const R_CONSTANTS: [u8;11] = [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1B, 0x36];
 
-pub const SBOX: [u8; 256] = [
+pub static SBOX: [u8; 256] = [
     0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
     0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
     0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
@@ -19,7 +19,7 @@ pub const SBOX: [u8; 256] = [
     0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16
 ];
 
-pub const INV_SBOX: [u8; 256] = [
+pub static INV_SBOX: [u8; 256] = [
     0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
     0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
     0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
@@ -38,3 +38,54 @@ pub const INV_SBOX: [u8; 256] = [
     0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d 
 ];
 
+
+
+// ----------------------------
+// T-tables (generated at compile time)
+// ----------------------------
+// TE0[x] packs the MixColumns column (2s, s, s, 3s) for s = SBOX[x] into a
+// big-endian word; TD0[x] packs (14s, 9s, 13s, 11s) for s = INV_SBOX[x].
+// TE1..TE3 / TD1..TD3 are the same tables rotated right by 8, 16 and 24 bits.
+pub(crate) const fn gf_mul_const(mut a: u8, mut b: u8) -> u8 {
+    let mut result: u8 = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            result ^= a;
+        }
+        let high_bit = a & 0x80;
+        a <<= 1;
+        if high_bit != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    result
+}
+
+const fn build_table(sbox: &[u8; 256], coeffs: [u8; 4], rotation: u32) -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let s = sbox[i];
+        let word = ((gf_mul_const(s, coeffs[0]) as u32) << 24)
+            | ((gf_mul_const(s, coeffs[1]) as u32) << 16)
+            | ((gf_mul_const(s, coeffs[2]) as u32) << 8)
+            | (gf_mul_const(s, coeffs[3]) as u32);
+        table[i] = word.rotate_right(rotation);
+        i += 1;
+    }
+    table
+}
+
+const ENC_COEFFS: [u8; 4] = [0x02, 0x01, 0x01, 0x03];
+const DEC_COEFFS: [u8; 4] = [0x0e, 0x09, 0x0d, 0x0b];
+
+pub static TE0: [u32; 256] = build_table(&SBOX, ENC_COEFFS, 0);
+pub static TE1: [u32; 256] = build_table(&SBOX, ENC_COEFFS, 8);
+pub static TE2: [u32; 256] = build_table(&SBOX, ENC_COEFFS, 16);
+pub static TE3: [u32; 256] = build_table(&SBOX, ENC_COEFFS, 24);
+
+pub static TD0: [u32; 256] = build_table(&INV_SBOX, DEC_COEFFS, 0);
+pub static TD1: [u32; 256] = build_table(&INV_SBOX, DEC_COEFFS, 8);
+pub static TD2: [u32; 256] = build_table(&INV_SBOX, DEC_COEFFS, 16);
+pub static TD3: [u32; 256] = build_table(&INV_SBOX, DEC_COEFFS, 24);
@@ -1,12 +1,23 @@
 use std::{fmt,error::Error};
-use rand::{rngs::OsRng, RngCore};
+use rand::{rngs::{OsRng, StdRng}, RngCore, SeedableRng};
+use std::time::Instant;
 use crate::crypto::aes_constants::{SBOX, R_CONSTANTS, INV_SBOX};
+use crate::basics::encodings::hex_to_bytes;
+use crate::crypto::aes_ttable::{self, TTableKeys};
+use crate::crypto::aes_bitsliced::{self, BitslicedKeys, PARALLEL_BLOCKS};
+use crate::crypto::aes_ni::{self, AesNiKeys};
+use crate::crypto::secret::{SecretBytes, SecretKey};
+use crate::crypto::block_cipher::{ecb_encrypt, ecb_decrypt, cbc_encrypt, cbc_decrypt, ctr_apply};
 
 #[derive(Debug)]
 pub enum AesError {
     InvalidLength(&'static str), // e.g., "Ciphertext must be multiple of 16 bytes"
     InvalidHexChar(char),
     HexStringOddLength,
+    AuthenticationFailed,
+    IntegrityCheckFailed,
+    Padding(PaddingError),
+    NotEcb,
 }
 
 impl fmt::Display for AesError {
@@ -15,116 +26,239 @@ impl fmt::Display for AesError {
             AesError::InvalidLength(msg) => write!(f, "{}", msg),
             AesError::InvalidHexChar(c) => write!(f, "Invalid hex character '{}'", c),
             AesError::HexStringOddLength => write!(f, "Hex string must have even length"),
+            AesError::AuthenticationFailed => write!(f, "Authentication tag mismatch"),
+            AesError::IntegrityCheckFailed => write!(f, "Key unwrap integrity check failed"),
+            AesError::Padding(err) => write!(f, "{}", err),
+            AesError::NotEcb => write!(f, "Oracle output shows no repeated blocks: not ECB"),
         }
     }
 }
 
 impl std::error::Error for AesError {}
 
+impl From<PaddingError> for AesError {
+    fn from(err: PaddingError) -> Self {
+        AesError::Padding(err)
+    }
+}
+
+/// Why PKCS#7 padding or unpadding failed.
+///
+/// `pkcs7_unpadding` reports the specific cause, which is fine for local data
+/// but is exactly the padding oracle an attacker wants. `pkcs7_unpadding_ct`
+/// only ever reports `Invalid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingError {
+    EmptyInput,
+    /// Block size outside 1..=255, which one pad byte cannot describe.
+    InvalidBlockSize(usize),
+    /// Input is not a whole number of blocks.
+    NotBlockAligned,
+    InvalidLength,
+    InvalidBytes,
+    /// Padding rejected by the constant-time check; the cause is not reported.
+    Invalid,
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingError::EmptyInput => write!(f, "Input is empty"),
+            PaddingError::InvalidBlockSize(size) => write!(f, "Block size {} is not in 1..=255", size),
+            PaddingError::NotBlockAligned => write!(f, "Input is not a multiple of the block size"),
+            PaddingError::InvalidLength => write!(f, "Invalid padding length"),
+            PaddingError::InvalidBytes => write!(f, "Invalid padding bytes"),
+            PaddingError::Invalid => write!(f, "Invalid padding"),
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+/// Block-function implementation used by an `AesStandard` context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AesBackend {
+    /// Straightforward FIPS-197 transformations (SubBytes, ShiftRows, MixColumns).
+    #[default]
+    Reference,
+    /// Precomputed T-tables: each round is 16 table lookups and XORs.
+    TTable,
+    /// Constant-time bitsliced circuit, eight blocks per call.
+    Bitsliced,
+    /// Hardware AES instructions (x86_64 AES-NI), checked at runtime.
+    AesNi,
+}
+
+impl AesBackend {
+    /// Fastest backend available on this CPU: AES-NI if present, else the reference path.
+    pub fn detect() -> Self {
+        if aes_ni::is_supported() {
+            AesBackend::AesNi
+        } else {
+            AesBackend::Reference
+        }
+    }
+}
+
+/// Point inside an encryption round at which a hook sees the state.
+///
+/// Round 0 only has `Input` (before the whitening key); rounds `1..=rounds`
+/// report `Start`, `SubBytes`, `ShiftRows`, `MixColumns` (not in the final
+/// round), and the final round ends with `Output`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundStep {
+    Input,
+    Start,
+    SubBytes,
+    ShiftRows,
+    MixColumns,
+    Output,
+}
+
+/// One line of an encryption trace, e.g. `round[ 1].s_box`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub round: usize,
+    /// FIPS-197 Appendix C label: input, k_sch, start, s_box, s_row, m_col or output.
+    pub label: &'static str,
+    pub value: [u8; 16],
+}
+
+/// Every intermediate state of one block encryption.
+///
+/// `Display` prints it in the FIPS-197 Appendix C layout so it can be diffed
+/// against the spec line by line.
+#[derive(Clone, Debug, Default)]
+pub struct AesTrace {
+    pub entries: Vec<TraceEntry>,
+}
+
+impl AesTrace {
+    /// The ciphertext (the `output` line).
+    pub fn output(&self) -> Option<[u8; 16]> {
+        self.entries.iter().find(|e| e.label == "output").map(|e| e.value)
+    }
+
+    /// Value recorded for `round[round].label`, if any.
+    pub fn get(&self, round: usize, label: &str) -> Option<[u8; 16]> {
+        self.entries.iter().find(|e| e.round == round && e.label == label).map(|e| e.value)
+    }
+}
+
+impl fmt::Display for AesTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "round[{:>2}].{:<9}{}", entry.round, entry.label, hex::encode(entry.value))?;
+        }
+        Ok(())
+    }
+}
+
+// Backend-specific key material derived from the expanded key
+enum BackendKeys {
+    Reference,
+    TTable(TTableKeys),
+    Bitsliced(BitslicedKeys),
+    AesNi(AesNiKeys),
+}
+
 /// | Variant | Key length | Expanded key size | Rounds |
 /// | ------- | ---------- | ----------------- | ------ |
 /// | AES-128 | 16 bytes   | 176 bytes         | 10     |
 /// | AES-192 | 24 bytes   | 208 bytes         | 12     |
 /// | AES-256 | 32 bytes   | 240 bytes         | 14     |
 pub struct AesStandard {
-    expanded_key: Vec<u8>, // variable-size expanded key (176/208/240)
+    expanded_key: SecretBytes, // variable-size expanded key (176/208/240), wiped on drop
     rounds: usize,         // number of rounds (10/12/14)
     _nk: usize,             // words in original key (4/6/8)
+    backend: AesBackend,
+    backend_keys: BackendKeys,
 }
 
 impl AesStandard {
     /// Create a new AES context: accepts 16, 24 or 32 byte keys.
     /// Defaults to AES block size = 16 bytes (Nb = 4).
+    /// Uses AES-NI when the CPU supports it, the software path otherwise.
     pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        Self::with_backend(key, AesBackend::detect())
+    }
+
+    /// Create a new AES context using the given block-function backend.
+    /// Asking for `AesNi` on a CPU without it falls back to `Reference`;
+    /// `backend()` reports what was actually selected.
+    pub fn with_backend(key: &[u8], backend: AesBackend) -> Result<Self, AesError> {
+        Self::build(key, backend, None)
+    }
+
+    /// Create a reduced-round AES context for cryptanalysis (e.g. 4 or 5 rounds).
+    /// The key schedule is the standard one truncated to `rounds + 1` round keys,
+    /// and the last round still omits MixColumns.
+    pub fn with_rounds(key: &[u8], rounds: usize) -> Result<Self, AesError> {
+        Self::build(key, AesBackend::detect(), Some(rounds))
+    }
+
+    fn build(key: &[u8], backend: AesBackend, custom_rounds: Option<usize>) -> Result<Self, AesError> {
         match key.len() {
             16 | 24 | 32 => {}
             _ => return Err(AesError::InvalidLength("Invalid key size: must be 16, 24 or 32 bytes")),
         }
 
         let _nk: usize = key.len() / 4;         // 4, 6 or 8
-        let rounds: usize = _nk + 6;            // Nr = Nk + 6 (10, 12, 14)
+        let full_rounds: usize = _nk + 6;       // Nr = Nk + 6 (10, 12, 14)
+        let rounds: usize = match custom_rounds {
+            None => full_rounds,
+            Some(r) if (1..=full_rounds).contains(&r) => r,
+            Some(_) => return Err(AesError::InvalidLength("Round count must be between 1 and Nk + 6")),
+        };
         let nb: usize = 4usize;                // AES standard block size: Nb = 4
         let total_words: usize = (rounds + 1) * nb; // number of 4-byte words in expanded key
         let expanded_bytes: usize = total_words * 4;
 
-        let mut expanded_key: Vec<u8> = vec![0u8; expanded_bytes];
+        let mut expanded_key = SecretBytes::zeroed(expanded_bytes);
         Self::key_expansion_general(key, _nk, rounds, &mut expanded_key);
 
-        Ok(Self { expanded_key, rounds, _nk })
+        let (backend, backend_keys) = match backend {
+            AesBackend::Reference => (backend, BackendKeys::Reference),
+            AesBackend::TTable => (backend, BackendKeys::TTable(TTableKeys::new(&expanded_key, rounds))),
+            AesBackend::Bitsliced => (backend, BackendKeys::Bitsliced(BitslicedKeys::new(&expanded_key, rounds))),
+            AesBackend::AesNi => match AesNiKeys::new(&expanded_key, rounds) {
+                Some(keys) => (backend, BackendKeys::AesNi(keys)),
+                None => (AesBackend::Reference, BackendKeys::Reference),
+            },
+        };
+
+        Ok(Self { expanded_key, rounds, _nk, backend, backend_keys })
     }
 
     // ----------------------------
-    // Modes (ECB/CBC) — unchanged except expanded_key is Vec
+    // Modes: wrappers over the generic modes in block_cipher, which pick
+    // up this backend's bulk path through encrypt_blocks/decrypt_blocks
     // ----------------------------
     pub fn encrypt_ecb(&self, plaintext: &[u8]) -> Result<Vec<u8>, AesError> {
-        if plaintext.len() % 16 != 0 {
-            return Err(AesError::InvalidLength(
-                "Plaintext must be multiple of 16 bytes",
-            ));
-        }
-
-        let mut out = Vec::with_capacity(plaintext.len());
-        for chunk in plaintext.chunks_exact(16) {
-            let mut block = [0u8; 16];
-            block.copy_from_slice(chunk);
-            out.extend_from_slice(&self.encrypt_block(&block));
-        }
-        Ok(out)
+        ecb_encrypt(self, plaintext)
     }
 
     pub fn decrypt_ecb(&self, ciphertext: &[u8]) -> Result<Vec<u8>, AesError> {
-        if ciphertext.len() % 16 != 0 {
-            return Err(AesError::InvalidLength(
-                "Ciphertext must be multiple of 16 bytes",
-            ));
-        }
-
-        let mut out = Vec::with_capacity(ciphertext.len());
-        for chunk in ciphertext.chunks_exact(16) {
-            let mut block = [0u8; 16];
-            block.copy_from_slice(chunk);
-            out.extend_from_slice(&self.decrypt_block(&block));
-        }
-        Ok(out)
+        ecb_decrypt(self, ciphertext)
     }
 
     pub fn encrypt_cbc(&self, plaintext: &[u8], iv: &[u8; 16]) -> Result<Vec<u8>, AesError> {
-        if plaintext.len() % 16 != 0 {
-            return Err(AesError::InvalidLength(
-                "Plaintext must be multiple of 16 bytes",
-            ));
-        }
-        let mut ciphertext = Vec::with_capacity(plaintext.len());
-        let mut prev = *iv;
-        for chunk in plaintext.chunks_exact(16) {
-            let mut block = [0u8; 16];
-            block.copy_from_slice(chunk);
-            Self::xor_in_place(&mut block, &prev);
-            let enc = self.encrypt_block(&block);
-            ciphertext.extend_from_slice(&enc);
-            prev = enc;
-        }
-        Ok(ciphertext)
+        cbc_encrypt(self, plaintext, iv)
     }
 
     pub fn decrypt_cbc(&self, ciphertext: &[u8], iv: &[u8; 16]) -> Result<Vec<u8>, AesError> {
-        if ciphertext.len() % 16 != 0 {
-            return Err(AesError::InvalidLength(
-                "Ciphertext must be multiple of 16 bytes",
-            ));
-        }
-        let mut plaintext = Vec::with_capacity(ciphertext.len());
-        let mut prev = *iv;
-        for chunk in ciphertext.chunks_exact(16) {
-            let mut block = [0u8; 16];
-            block.copy_from_slice(chunk);
-            let dec = self.decrypt_block(&block);
-            let mut plain_block = dec;
-            Self::xor_in_place(&mut plain_block, &prev);
-            plaintext.extend_from_slice(&plain_block);
-            prev = block;
-        }
-        Ok(plaintext)
+        cbc_decrypt(self, ciphertext, iv)
+    }
+
+    /// CTR mode (NIST SP 800-38A): keystream E(T_1), E(T_2), ... where the
+    /// counter block is incremented as a 128-bit big-endian integer. Works on
+    /// any length; decryption is the same operation.
+    pub fn encrypt_ctr(&self, data: &[u8], initial_counter: &[u8; 16]) -> Vec<u8> {
+        ctr_apply(self, data, initial_counter).expect("counter block is 16 bytes")
+    }
+
+    pub fn decrypt_ctr(&self, data: &[u8], initial_counter: &[u8; 16]) -> Vec<u8> {
+        self.encrypt_ctr(data, initial_counter)
     }
 
     // ----------------------------
@@ -183,37 +317,251 @@ impl AesStandard {
         }
     }
 
+    /// Run the key schedule backwards from `nk` consecutive known words.
+    ///
+    /// `known` holds words `start_word .. start_word + nk` of the expanded key;
+    /// since W[i - Nk] = W[i] ^ temp(W[i - 1]), every earlier word follows.
+    /// Returns the expanded key up to and including the known words.
+    pub(crate) fn key_expansion_inverse(known: &[u8], start_word: usize, nk: usize) -> SecretBytes {
+        let end_word = start_word + nk;
+        let mut words = SecretBytes::zeroed(end_word * 4);
+        words[start_word * 4..].copy_from_slice(&known[..nk * 4]);
+
+        let mut temp = [0u8; 4];
+        for i in (nk..end_word).rev() {
+            let prev = (i - 1) * 4;
+            temp.copy_from_slice(&words[prev..prev + 4]);
+
+            if i % nk == 0 {
+                temp.rotate_left(1);
+                for b in temp.iter_mut() {
+                    *b = SBOX[*b as usize];
+                }
+                temp[0] ^= R_CONSTANTS[i / nk];
+            } else if nk > 6 && (i % nk) == 4 {
+                for b in temp.iter_mut() {
+                    *b = SBOX[*b as usize];
+                }
+            }
+
+            // W[i - Nk] = W[i] ^ temp
+            let out_start = (i - nk) * 4;
+            for j in 0..4 {
+                words[out_start + j] = words[i * 4 + j] ^ temp[j];
+            }
+        }
+        words
+    }
+
+    /// Derive the cipher key and full expanded key from leaked round keys.
+    ///
+    /// `round_keys` starts at round key `round`: one round key (16 bytes) for
+    /// AES-128, two consecutive round keys (32 bytes) for AES-192 and AES-256,
+    /// since those schedules need Nk = 6 or 8 consecutive words to run backwards.
+    /// Returns `(cipher_key, expanded_key)`, both wiped on drop; any bytes
+    /// beyond the Nk words used are checked against the re-expanded schedule.
+    pub fn invert_key_schedule(round_keys: &[u8], round: usize, key_len: usize) -> Result<(SecretKey, SecretBytes), AesError> {
+        let (needed, rounds) = match key_len {
+            16 => (16, 10),
+            24 => (32, 12),
+            32 => (32, 14),
+            _ => return Err(AesError::InvalidLength("Invalid key size: must be 16, 24 or 32 bytes")),
+        };
+        if round_keys.len() != needed {
+            return Err(AesError::InvalidLength("Need one round key for AES-128, two consecutive for AES-192/256"));
+        }
+        if round * 16 + needed > (rounds + 1) * 16 {
+            return Err(AesError::InvalidLength("Round index out of range for this key size"));
+        }
+
+        let nk = key_len / 4;
+        let words = Self::key_expansion_inverse(round_keys, round * 4, nk);
+        let cipher_key = SecretKey::from_slice(&words[..key_len]);
+
+        let mut expanded_key = SecretBytes::zeroed((rounds + 1) * 16);
+        Self::key_expansion_general(&cipher_key, nk, rounds, &mut expanded_key);
+        if expanded_key[round * 16..round * 16 + needed] != *round_keys {
+            return Err(AesError::InvalidLength("Round keys are not consistent with one key schedule"));
+        }
+        Ok((cipher_key, expanded_key))
+    }
+
     pub fn rounds(&self) -> usize {
         self.rounds
     }
 
+    /// Round key `round` (0 = whitening key, `rounds()` = last round key).
+    pub fn round_key(&self, round: usize) -> SecretBytes {
+        SecretBytes::from_slice(&self.expanded_key[round * 16..round * 16 + 16])
+    }
+
+    // Round key as a plain block, for traces (which record secrets by design)
+    fn round_key_block(&self, round: usize) -> [u8; 16] {
+        let mut rk = [0u8; 16];
+        rk.copy_from_slice(&self.expanded_key[round * 16..round * 16 + 16]);
+        rk
+    }
+
+    pub fn backend(&self) -> AesBackend {
+        self.backend
+    }
+
     // ----------------------------
     // AES Block Operations (assumes Nb=4 / 16-byte state)
     // ----------------------------
     pub fn encrypt_block(&self, input: &[u8; 16]) -> [u8; 16] {
+        match &self.backend_keys {
+            BackendKeys::Reference => self.encrypt_block_reference(input),
+            BackendKeys::TTable(keys) => aes_ttable::encrypt_block(keys, self.rounds, input),
+            BackendKeys::Bitsliced(keys) => {
+                // a single block still runs the full 8-lane circuit
+                let mut batch = [[0u8; 16]; PARALLEL_BLOCKS];
+                batch[0] = *input;
+                aes_bitsliced::encrypt_blocks(keys, self.rounds, &batch)[0]
+            }
+            BackendKeys::AesNi(keys) => {
+                let mut block = [*input];
+                aes_ni::encrypt_blocks(keys, &mut block);
+                block[0]
+            }
+        }
+    }
+
+    pub fn decrypt_block(&self, input: &[u8; 16]) -> [u8; 16] {
+        match &self.backend_keys {
+            BackendKeys::Reference => self.decrypt_block_reference(input),
+            BackendKeys::TTable(keys) => aes_ttable::decrypt_block(keys, self.rounds, input),
+            BackendKeys::Bitsliced(keys) => {
+                let mut batch = [[0u8; 16]; PARALLEL_BLOCKS];
+                batch[0] = *input;
+                aes_bitsliced::decrypt_blocks(keys, self.rounds, &batch)[0]
+            }
+            BackendKeys::AesNi(keys) => {
+                let mut block = [*input];
+                aes_ni::decrypt_blocks(keys, &mut block);
+                block[0]
+            }
+        }
+    }
+
+    /// Encrypt many independent blocks; the bitsliced backend fills all eight
+    /// lanes and AES-NI interleaves eight blocks through the pipeline.
+    pub fn encrypt_blocks(&self, blocks: &mut [[u8; 16]]) {
+        match &self.backend_keys {
+            BackendKeys::AesNi(keys) => aes_ni::encrypt_blocks(keys, blocks),
+            BackendKeys::Bitsliced(keys) => {
+                for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+                    let mut batch = [[0u8; 16]; PARALLEL_BLOCKS];
+                    batch[..chunk.len()].copy_from_slice(chunk);
+                    let out = aes_bitsliced::encrypt_blocks(keys, self.rounds, &batch);
+                    chunk.copy_from_slice(&out[..chunk.len()]);
+                }
+            }
+            _ => {
+                for block in blocks.iter_mut() {
+                    *block = self.encrypt_block(block);
+                }
+            }
+        }
+    }
+
+    /// Decrypt many independent blocks (see `encrypt_blocks`).
+    pub fn decrypt_blocks(&self, blocks: &mut [[u8; 16]]) {
+        match &self.backend_keys {
+            BackendKeys::AesNi(keys) => aes_ni::decrypt_blocks(keys, blocks),
+            BackendKeys::Bitsliced(keys) => {
+                for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+                    let mut batch = [[0u8; 16]; PARALLEL_BLOCKS];
+                    batch[..chunk.len()].copy_from_slice(chunk);
+                    let out = aes_bitsliced::decrypt_blocks(keys, self.rounds, &batch);
+                    chunk.copy_from_slice(&out[..chunk.len()]);
+                }
+            }
+            _ => {
+                for block in blocks.iter_mut() {
+                    *block = self.decrypt_block(block);
+                }
+            }
+        }
+    }
+
+    /// Reference FIPS-197 encryption, regardless of the selected backend.
+    pub fn encrypt_block_reference(&self, input: &[u8; 16]) -> [u8; 16] {
+        self.encrypt_block_hooked(input, |_, _, _| {})
+    }
+
+    /// Reference encryption recording every intermediate state and round key.
+    pub fn encrypt_block_traced(&self, input: &[u8; 16]) -> AesTrace {
+        let mut entries = Vec::new();
+        self.encrypt_block_hooked(input, |round, step, state| {
+            let mut push = |label, value| entries.push(TraceEntry { round, label, value });
+            match step {
+                RoundStep::Input => {
+                    push("input", *state);
+                    push("k_sch", self.round_key_block(0));
+                }
+                RoundStep::Start => push("start", *state),
+                RoundStep::SubBytes => push("s_box", *state),
+                RoundStep::ShiftRows => {
+                    push("s_row", *state);
+                    // the final round has no MixColumns, its key follows ShiftRows
+                    if round == self.rounds {
+                        push("k_sch", self.round_key_block(round));
+                    }
+                }
+                RoundStep::MixColumns => {
+                    push("m_col", *state);
+                    push("k_sch", self.round_key_block(round));
+                }
+                RoundStep::Output => push("output", *state),
+            }
+        });
+        AesTrace { entries }
+    }
+
+    /// Reference encryption that hands the state to `hook` after every step.
+    ///
+    /// The hook gets `(round, step, &mut state)` and may modify the state, which
+    /// is how faults are injected; see `RoundStep` for when it is called.
+    pub fn encrypt_block_hooked(
+        &self,
+        input: &[u8; 16],
+        mut hook: impl FnMut(usize, RoundStep, &mut [u8; 16]),
+    ) -> [u8; 16] {
         let mut state = *input;
         // Initial round key (round 0)
+        hook(0, RoundStep::Input, &mut state);
         Self::xor_in_place(&mut state, &self.expanded_key[0..16]);
 
         // main rounds 1 .. rounds-1
         for round in 1..self.rounds {
+            hook(round, RoundStep::Start, &mut state);
             Self::sub_bytes(&mut state);
+            hook(round, RoundStep::SubBytes, &mut state);
             Self::shift_rows(&mut state);
+            hook(round, RoundStep::ShiftRows, &mut state);
             Self::mix_columns(&mut state);
+            hook(round, RoundStep::MixColumns, &mut state);
             let rk_start = round * 16;
             Self::xor_in_place(&mut state, &self.expanded_key[rk_start..rk_start + 16]);
         }
 
         // final round
+        let last = self.rounds;
+        hook(last, RoundStep::Start, &mut state);
         Self::sub_bytes(&mut state);
+        hook(last, RoundStep::SubBytes, &mut state);
         Self::shift_rows(&mut state);
-        let final_rk_start = self.rounds * 16;
+        hook(last, RoundStep::ShiftRows, &mut state);
+        let final_rk_start = last * 16;
         Self::xor_in_place(&mut state, &self.expanded_key[final_rk_start..final_rk_start + 16]);
+        hook(last, RoundStep::Output, &mut state);
 
         state
     }
 
-    pub fn decrypt_block(&self, input: &[u8; 16]) -> [u8; 16] {
+    /// Reference FIPS-197 decryption, regardless of the selected backend.
+    pub fn decrypt_block_reference(&self, input: &[u8; 16]) -> [u8; 16] {
         let mut state = *input;
 
         // Initial add round key (last)
@@ -328,7 +676,7 @@ impl AesStandard {
     // ----------------------------
     // GF(2^8) multiply
     // ----------------------------
-    fn gmul(mut a: u8, mut b: u8) -> u8 {
+    pub(crate) fn gmul(mut a: u8, mut b: u8) -> u8 {
         let mut result: u8 = 0;
         const IRREDUCIBLE_POLY: u8 = 0x1b;
         while b != 0 {
@@ -346,38 +694,97 @@ impl AesStandard {
     }
 }
 
-pub fn pkcs7_padding(bytes: &[u8], block_size: usize) -> Vec<u8> {
+pub fn pkcs7_padding(bytes: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    if block_size == 0 || block_size > 255 {
+        return Err(PaddingError::InvalidBlockSize(block_size));
+    }
     let pad_len: usize = block_size - (bytes.len() % block_size);
     let mut out: Vec<u8> = bytes.to_vec();
-    out.extend(std::iter::repeat(pad_len as u8).take(pad_len));
-    out
+    out.resize(bytes.len() + pad_len, pad_len as u8);
+    Ok(out)
 }
 
 
-pub fn pkcs7_unpadding(bytes: &[u8]) -> Result<Vec<u8>, &'static str> {
+pub fn pkcs7_unpadding(bytes: &[u8]) -> Result<Vec<u8>, PaddingError> {
     if bytes.is_empty() {
-        return Err("Input is empty");
+        return Err(PaddingError::EmptyInput);
     }
 
     let pad_len = *bytes.last().unwrap() as usize;
 
     if pad_len == 0 || pad_len > bytes.len() {
-        return Err("Invalid padding length");
+        return Err(PaddingError::InvalidLength);
     }
 
     // Check that the last pad_len bytes are all the same value
     if !bytes[bytes.len() - pad_len..].iter().all(|&b| b as usize == pad_len) {
-        return Err("Invalid padding bytes");
+        return Err(PaddingError::InvalidBytes);
     }
 
     Ok(bytes[..bytes.len() - pad_len].to_vec())
 }
 
+/// PKCS#7 unpadding whose running time does not depend on the padding.
+///
+/// The input length is public, so it is checked up front; after that every
+/// byte of the last block is examined with masks and no early exit, and any
+/// failure is reported as the same `PaddingError::Invalid`. Only the final
+/// truncation depends on the (by then validated) pad length.
+pub fn pkcs7_unpadding_ct(bytes: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    if block_size == 0 || block_size > 255 {
+        return Err(PaddingError::InvalidBlockSize(block_size));
+    }
+    if bytes.is_empty() {
+        return Err(PaddingError::EmptyInput);
+    }
+    if !bytes.len().is_multiple_of(block_size) {
+        return Err(PaddingError::NotBlockAligned);
+    }
+
+    let last_block = &bytes[bytes.len() - block_size..];
+    let pad = last_block[block_size - 1];
+
+    // 0xff if a < b, else 0x00, without branching
+    let lt = |a: u8, b: u8| ((a as u16).wrapping_sub(b as u16) >> 8) as u8;
+
+    // bad if pad == 0 or pad > block_size
+    let mut bad = lt(pad, 1) | lt(block_size as u8, pad);
+    for (i, &byte) in last_block.iter().rev().enumerate() {
+        let in_pad = lt(i as u8, pad);
+        bad |= in_pad & (byte ^ pad);
+    }
+
+    if std::hint::black_box(bad) != 0 {
+        return Err(PaddingError::Invalid);
+    }
+    Ok(bytes[..bytes.len() - pad as usize].to_vec())
+}
+
+/// Random key from the OS RNG, wiped when dropped.
+pub fn gen_key(len: usize) -> SecretKey {
+    gen_key_with_rng(len, &mut OsRng)
+}
+
+/// Random key from `rng`, so a seeded run can be replayed.
+pub fn gen_key_with_rng(len: usize, rng: &mut impl RngCore) -> SecretKey {
+    SecretKey::from_rng(rng, len)
+}
+
+/// Random non-secret bytes (IVs, nonces, test data) from the OS RNG.
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    random_bytes_with_rng(len, &mut OsRng)
+}
+
+pub fn random_bytes_with_rng(len: usize, rng: &mut impl RngCore) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
 
-pub fn gen_key(len: usize) -> Vec<u8> {
-    let mut key: Vec<u8> = vec![0u8; len];
-    OsRng.fill_bytes(&mut key);
-    key
+/// Deterministic RNG for the `*_with_rng` functions: the same seed replays
+/// the same keys, IVs and choices.
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
 }
 
 pub fn _test_aes() -> Result<(), Box<dyn Error>> {
@@ -425,7 +832,8 @@ pub fn _test_aes() -> Result<(), Box<dyn Error>> {
     if c == encrypted_state {
         println!("Encrypted matches ciphertext");
     } else {
-        println!("Encryption mismatch!");
+        println!("Encryption mismatch! Trace to diff against FIPS-197 C.1:");
+        print!("{}", aes.encrypt_block_traced(&base_state));
     }
 
     let decrypted: Vec<u8> = aes.decrypt_ecb(&encrypted_state)?;
@@ -436,3 +844,195 @@ pub fn _test_aes() -> Result<(), Box<dyn Error>> {
     }
     Ok(())
 }
+
+/// Compare a traced AES-128 encryption with lines from FIPS-197 Appendix C.1.
+pub fn _test_aes_trace() -> Result<(), Box<dyn Error>> {
+    let key = hex_to_bytes("000102030405060708090a0b0c0d0e0f")?;
+    let mut input = [0u8; 16];
+    input.copy_from_slice(&hex_to_bytes("00112233445566778899aabbccddeeff")?);
+
+    let aes = AesStandard::new(&key)?;
+    let trace = aes.encrypt_block_traced(&input);
+    print!("{}", trace);
+
+    let spec = [
+        "round[ 0].input    00112233445566778899aabbccddeeff",
+        "round[ 0].k_sch    000102030405060708090a0b0c0d0e0f",
+        "round[ 1].start    00102030405060708090a0b0c0d0e0f0",
+        "round[ 1].s_box    63cab7040953d051cd60e0e7ba70e18c",
+        "round[ 1].s_row    6353e08c0960e104cd70b751bacad0e7",
+        "round[ 1].m_col    5f72641557f5bc92f7be3b291db9f91a",
+        "round[ 1].k_sch    d6aa74fdd2af72fadaa678f1d6ab76fe",
+        "round[ 2].start    89d810e8855ace682d1843d8cb128fe4",
+        "round[10].k_sch    13111d7fe3944a17f307a78b4d2b30c5",
+        "round[10].output   69c4e0d86a7b0430d8cdb78070b4c55a",
+    ];
+    let printed = trace.to_string();
+    let missing: Vec<&&str> = spec.iter().filter(|line| !printed.lines().any(|l| l == **line)).collect();
+    if missing.is_empty() {
+        println!("Trace matches FIPS-197 C.1 ({} lines)", printed.lines().count());
+    } else {
+        for line in missing {
+            println!("Missing spec line: {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Invert the key schedule from every possible round index for all key sizes.
+pub fn _test_inverse_key_schedule() -> Result<(), Box<dyn Error>> {
+    for key_len in [16, 24, 32] {
+        let key = gen_key(key_len);
+        let aes = AesStandard::new(&key)?;
+        let span = if key_len == 16 { 1 } else { 2 };
+
+        let mut failures = 0;
+        for round in 0..=(aes.rounds() + 1 - span) {
+            let leaked: Vec<u8> = (round..round + span).flat_map(|r| aes.round_key(r).to_vec()).collect();
+            let (cipher_key, expanded) = AesStandard::invert_key_schedule(&leaked, round, key_len)?;
+            let all_round_keys: Vec<u8> = (0..=aes.rounds()).flat_map(|r| aes.round_key(r).to_vec()).collect();
+            if cipher_key != key || expanded[..] != all_round_keys[..] {
+                failures += 1;
+            }
+        }
+        println!("AES-{} inverse key schedule: {} failures", key_len * 8, failures);
+    }
+
+    // FIPS-197 A.1: last round key of 2b7e1516... is d014f9a8c9ee2589e13f0cc8b6630ca6
+    let last = hex_to_bytes("d014f9a8c9ee2589e13f0cc8b6630ca6")?;
+    let (cipher_key, _) = AesStandard::invert_key_schedule(&last, 10, 16)?;
+    println!("FIPS-197 AES-128 master key: {}", hex::encode(cipher_key));
+    Ok(())
+}
+
+/// Cross-check every backend against the reference block functions on
+/// random keys and blocks, for all three key sizes.
+pub fn _test_aes_backends() -> Result<(), Box<dyn Error>> {
+    let backends = [AesBackend::TTable, AesBackend::Bitsliced, AesBackend::AesNi];
+    if !aes_ni::is_supported() {
+        println!("AES-NI not available: AesNi falls back to the reference path");
+    }
+    for key_len in [16, 24, 32] {
+        for backend in backends {
+            let mut mismatches = 0;
+            for _ in 0..64 {
+                let key = gen_key(key_len);
+                let block: [u8; 16] = random_bytes(16).try_into().unwrap();
+                let aes = AesStandard::with_backend(&key, backend)?;
+
+                let expected = aes.encrypt_block_reference(&block);
+                if aes.encrypt_block(&block) != expected || aes.decrypt_block(&expected) != block {
+                    mismatches += 1;
+                }
+            }
+
+            // bulk path, including a partial batch for the bitsliced backend
+            let aes = AesStandard::with_backend(&gen_key(key_len), backend)?;
+            let plaintext = random_bytes(16 * 11);
+            let reference: Vec<u8> = plaintext
+                .chunks_exact(16)
+                .flat_map(|c| aes.encrypt_block_reference(&c.try_into().unwrap()))
+                .collect();
+            let ciphertext = aes.encrypt_ecb(&plaintext)?;
+            if ciphertext != reference || aes.decrypt_ecb(&ciphertext)? != plaintext {
+                mismatches += 1;
+            }
+            println!("AES-{} {:?}: {} mismatches", key_len * 8, backend, mismatches);
+        }
+    }
+
+    // NIST SP 800-38A F.5.1 (CTR-AES128.Encrypt), on every backend
+    let key = hex_to_bytes("2b7e151628aed2a6abf7158809cf4f3c")?;
+    let counter: [u8; 16] = hex_to_bytes("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")?.try_into().unwrap();
+    let plaintext = hex_to_bytes(
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710",
+    )?;
+    let expected = hex_to_bytes(
+        "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff\
+         5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee",
+    )?;
+    for backend in [AesBackend::Reference, AesBackend::TTable, AesBackend::Bitsliced, AesBackend::AesNi] {
+        let aes = AesStandard::with_backend(&key, backend)?;
+        let ciphertext = aes.encrypt_ctr(&plaintext, &counter);
+        let ok = ciphertext == expected && aes.decrypt_ctr(&ciphertext[..50], &counter) == plaintext[..50];
+        println!("CTR {:?}: {}", aes.backend(), if ok { "matches SP 800-38A" } else { "mismatch!" });
+    }
+    Ok(())
+}
+
+/// Time `blocks` block encryptions per backend (ECB, so the bitsliced backend
+/// runs full batches) and report the speedup over the reference path.
+pub fn _bench_aes_backends(blocks: usize) -> Result<(), Box<dyn Error>> {
+    let key = gen_key(16);
+    let mut reference_secs = 0.0f64;
+    let batch: usize = 1024;
+    let plaintext = vec![0u8; 16 * batch];
+    let blocks = blocks.div_ceil(batch) * batch;
+
+    for backend in [AesBackend::Reference, AesBackend::TTable, AesBackend::Bitsliced, AesBackend::AesNi] {
+        let aes = AesStandard::with_backend(&key, backend)?;
+        let backend = aes.backend();
+        let mut block = [0u8; 16];
+        let start: Instant = Instant::now();
+        for _ in 0..blocks / batch {
+            let ciphertext = aes.encrypt_ecb(&plaintext)?;
+            block.copy_from_slice(&ciphertext[..16]);
+        }
+        let secs = start.elapsed().as_secs_f64();
+        if backend == AesBackend::Reference {
+            reference_secs = secs;
+        }
+        println!(
+            "{:<10} {} blocks in {:.3}s ({:.1} MB/s, {:.2}x) [{:02x}]",
+            format!("{:?}", backend), blocks, secs,
+            (blocks * 16) as f64 / secs / 1e6, reference_secs / secs, block[0],
+        );
+    }
+    Ok(())
+}
+
+/// PKCS#7 edge cases: the strict and constant-time unpadders must agree on
+/// every last byte value, and bad block sizes are rejected up front.
+pub fn _test_padding() -> Result<(), Box<dyn Error>> {
+    let cases = [
+        (b"ICE ICE BABY\x04\x04\x04\x04" as &[u8], Ok(b"ICE ICE BABY" as &[u8])),
+        (b"ICE ICE BABY\x05\x05\x05\x05", Err(PaddingError::InvalidBytes)),
+        (b"ICE ICE BABY\x01\x02\x03\x04", Err(PaddingError::InvalidBytes)),
+        (b"ICE ICE BABY\x00\x00\x00\x00", Err(PaddingError::InvalidLength)),
+        (b"", Err(PaddingError::EmptyInput)),
+    ];
+    for (input, expected) in cases {
+        let strict = pkcs7_unpadding(input);
+        let constant_time = pkcs7_unpadding_ct(input, 16);
+        let ok = strict.as_deref().map_err(|e| *e) == expected
+            && match expected {
+                Ok(_) => constant_time == strict,
+                Err(PaddingError::EmptyInput) => constant_time == Err(PaddingError::EmptyInput),
+                Err(_) => constant_time == Err(PaddingError::Invalid),
+            };
+        println!("{:?} -> {:?}: {}", String::from_utf8_lossy(input), strict, if ok { "ok" } else { "mismatch!" });
+    }
+
+    // every possible last byte, with a valid run of that byte before it
+    let mut disagreements = 0;
+    for pad in 0..=255u8 {
+        let mut block = [pad; 16];
+        block[0] = 0xaa;
+        let strict = pkcs7_unpadding(&block).ok();
+        if strict != pkcs7_unpadding_ct(&block, 16).ok() {
+            disagreements += 1;
+        }
+    }
+    println!("Strict vs constant-time over all 256 last bytes: {} disagreements", disagreements);
+
+    for block_size in [0, 256] {
+        match pkcs7_padding(b"data", block_size) {
+            Err(e) => println!("Block size {} rejected: {}", block_size, e),
+            Ok(_) => println!("Block size {} accepted!", block_size),
+        }
+    }
+    let padded = pkcs7_padding(b"YELLOW SUBMARINE", 16)?;
+    println!("Full-block pad appends {} bytes of 0x10", padded.len() - 16);
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::error::Error;
+use crate::crypto::aes_encyption::{AesStandard, AesError};
+use crate::crypto::gf128::Gf128;
+use crate::basics::encodings::hex_to_bytes;
+
+/// GHASH over `aad` and `ciphertext` (NIST SP 800-38D, section 6.4).
+/// Both inputs are zero-padded to 16 bytes and followed by the length block
+/// `len(A) || len(C)` in bits.
+pub fn ghash(h: Gf128, aad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(aad, ciphertext)
+        .into_iter()
+        .fold(Gf128::ZERO, |acc, block| (acc + block) * h)
+}
+
+/// The GHASH input blocks B_1 .. B_m for `aad` and `ciphertext`, length block last.
+pub fn ghash_blocks(aad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    let mut blocks: Vec<Gf128> = Vec::with_capacity(aad.len() / 16 + ciphertext.len() / 16 + 3);
+    blocks.extend(aad.chunks(16).map(Gf128::from_partial));
+    blocks.extend(ciphertext.chunks(16).map(Gf128::from_partial));
+
+    let mut len_block = [0u8; 16];
+    len_block[..8].copy_from_slice(&((aad.len() as u64) * 8).to_be_bytes());
+    len_block[8..].copy_from_slice(&((ciphertext.len() as u64) * 8).to_be_bytes());
+    blocks.push(Gf128::from_block(&len_block));
+    blocks
+}
+
+/// Increment the rightmost 32 bits of a counter block (inc32).
+fn inc32(block: &mut [u8; 16]) {
+    let ctr = u32::from_be_bytes([block[12], block[13], block[14], block[15]]).wrapping_add(1);
+    block[12..].copy_from_slice(&ctr.to_be_bytes());
+}
+
+impl AesStandard {
+    /// Hash subkey H = E_K(0^128).
+    pub fn gcm_hash_key(&self) -> Gf128 {
+        Gf128::from_block(&self.encrypt_block(&[0u8; 16]))
+    }
+
+    // Pre-counter block J0: IV || 0^31 || 1 for 96-bit IVs, GHASH(IV) otherwise
+    fn gcm_j0(&self, h: Gf128, nonce: &[u8]) -> [u8; 16] {
+        if nonce.len() == 12 {
+            let mut j0 = [0u8; 16];
+            j0[..12].copy_from_slice(nonce);
+            j0[15] = 1;
+            j0
+        } else {
+            ghash(h, &[], nonce).to_block()
+        }
+    }
+
+    // GCTR keystream starting at inc32(J0)
+    fn gcm_ctr(&self, j0: &[u8; 16], input: &[u8]) -> Vec<u8> {
+        let mut counter = *j0;
+        let mut out = Vec::with_capacity(input.len());
+        for chunk in input.chunks(16) {
+            inc32(&mut counter);
+            let keystream = self.encrypt_block(&counter);
+            out.extend(chunk.iter().zip(keystream.iter()).map(|(a, b)| a ^ b));
+        }
+        out
+    }
+
+    fn gcm_tag(&self, h: Gf128, j0: &[u8; 16], aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+        let s = ghash(h, aad, ciphertext);
+        let mask = Gf128::from_block(&self.encrypt_block(j0));
+        (s + mask).to_block()
+    }
+
+    /// AES-GCM authenticated encryption. Returns `(ciphertext, tag)`.
+    pub fn encrypt_gcm(&self, plaintext: &[u8], aad: &[u8], nonce: &[u8]) -> Result<(Vec<u8>, [u8; 16]), AesError> {
+        if nonce.is_empty() {
+            return Err(AesError::InvalidLength("GCM nonce must not be empty"));
+        }
+        let h = self.gcm_hash_key();
+        let j0 = self.gcm_j0(h, nonce);
+        let ciphertext = self.gcm_ctr(&j0, plaintext);
+        let tag = self.gcm_tag(h, &j0, aad, &ciphertext);
+        Ok((ciphertext, tag))
+    }
+
+    /// AES-GCM authenticated decryption; fails with `AuthenticationFailed` on a bad tag.
+    pub fn decrypt_gcm(&self, ciphertext: &[u8], aad: &[u8], nonce: &[u8], tag: &[u8; 16]) -> Result<Vec<u8>, AesError> {
+        if nonce.is_empty() {
+            return Err(AesError::InvalidLength("GCM nonce must not be empty"));
+        }
+        let h = self.gcm_hash_key();
+        let j0 = self.gcm_j0(h, nonce);
+        let expected = self.gcm_tag(h, &j0, aad, ciphertext);
+
+        // compare every byte so the check does not stop early
+        let diff = expected.iter().zip(tag.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(AesError::AuthenticationFailed);
+        }
+        Ok(self.gcm_ctr(&j0, ciphertext))
+    }
+}
+
+pub fn _test_gcm() -> Result<(), Box<dyn Error>> {
+    // SP 800-38D / McGrew-Viega test case 4 (AES-128, 60-byte plaintext, 20-byte AAD)
+    let key = hex_to_bytes("feffe9928665731c6d6a8f9467308308")?;
+    let nonce = hex_to_bytes("cafebabefacedbaddecaf888")?;
+    let aad = hex_to_bytes("feedfacedeadbeeffeedfacedeadbeefabaddad2")?;
+    let plaintext = hex_to_bytes(
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39",
+    )?;
+    let expected_ct = hex_to_bytes(
+        "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+         21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+    )?;
+    let expected_tag = hex_to_bytes("5bc94fbc3221a5db94fae95ae7121a47")?;
+
+    let aes = AesStandard::new(&key)?;
+    let (ciphertext, tag) = aes.encrypt_gcm(&plaintext, &aad, &nonce)?;
+    if ciphertext == expected_ct && tag.as_slice() == expected_tag.as_slice() {
+        println!("GCM ciphertext and tag match");
+    } else {
+        println!("GCM mismatch!");
+    }
+
+    let decrypted = aes.decrypt_gcm(&ciphertext, &aad, &nonce, &tag)?;
+    if decrypted == plaintext {
+        println!("GCM decryption matches plaintext");
+    } else {
+        println!("GCM decryption mismatch!");
+    }
+
+    let mut bad_tag = tag;
+    bad_tag[0] ^= 1;
+    match aes.decrypt_gcm(&ciphertext, &aad, &nonce, &bad_tag) {
+        Err(AesError::AuthenticationFailed) => println!("GCM rejects tampered tag"),
+        _ => println!("GCM accepted a tampered tag!"),
+    }
+    Ok(())
+}
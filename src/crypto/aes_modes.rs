@@ -0,0 +1,227 @@
+use std::error::Error;
+use crate::crypto::aes_encyption::{AesStandard, AesError};
+use crate::crypto::block_cipher::{ofb_apply, cfb_encrypt, cfb_decrypt, cfb8_encrypt, cfb8_decrypt, pcbc_encrypt, pcbc_decrypt};
+use crate::basics::encodings::hex_to_bytes;
+
+/// Ciphertext stealing variants for CBC (NIST SP 800-38A Addendum).
+///
+/// They differ only in where the truncated penultimate block goes:
+/// CS1 keeps CBC order, CS2 swaps the last two blocks only when the final
+/// block is partial, CS3 (Kerberos, RFC 3962) always swaps them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CtsVariant {
+    Cs1,
+    Cs2,
+    Cs3,
+}
+
+impl CtsVariant {
+    // whether C_n comes before the truncated C_{n-1}, given the final block length
+    fn swaps_last_blocks(self, tail: usize) -> bool {
+        match self {
+            CtsVariant::Cs1 => false,
+            CtsVariant::Cs2 => tail != 16,
+            CtsVariant::Cs3 => true,
+        }
+    }
+}
+
+impl AesStandard {
+    /// OFB mode (NIST SP 800-38A, 6.4): the keystream is E(IV), E(E(IV)), ...
+    /// independent of the data. Works on any length; decryption is the same operation.
+    pub fn encrypt_ofb(&self, data: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        ofb_apply(self, data, iv).expect("IV is 16 bytes")
+    }
+
+    pub fn decrypt_ofb(&self, data: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        self.encrypt_ofb(data, iv)
+    }
+
+    /// CFB-128 mode (NIST SP 800-38A, 6.3, full-block segments): C_i = P_i ^ E(C_{i-1}).
+    /// A trailing partial block uses the leading bytes of the last keystream block.
+    pub fn encrypt_cfb128(&self, plaintext: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        cfb_encrypt(self, plaintext, iv).expect("IV is 16 bytes")
+    }
+
+    pub fn decrypt_cfb128(&self, ciphertext: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        cfb_decrypt(self, ciphertext, iv).expect("IV is 16 bytes")
+    }
+
+    /// CFB-8 mode (NIST SP 800-38A, 6.3 with s = 8): one block encryption per
+    /// byte, the shift register takes each ciphertext byte in turn.
+    pub fn encrypt_cfb8(&self, plaintext: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        cfb8_encrypt(self, plaintext, iv).expect("IV is 16 bytes")
+    }
+
+    pub fn decrypt_cfb8(&self, ciphertext: &[u8], iv: &[u8; 16]) -> Vec<u8> {
+        cfb8_decrypt(self, ciphertext, iv).expect("IV is 16 bytes")
+    }
+
+    /// PCBC mode (Kerberos v4): C_i = E(P_i ^ P_{i-1} ^ C_{i-1}), with
+    /// P_0 ^ C_0 = IV. Like CBC, the input must be a multiple of 16 bytes.
+    pub fn encrypt_pcbc(&self, plaintext: &[u8], iv: &[u8; 16]) -> Result<Vec<u8>, AesError> {
+        pcbc_encrypt(self, plaintext, iv)
+    }
+
+    pub fn decrypt_pcbc(&self, ciphertext: &[u8], iv: &[u8; 16]) -> Result<Vec<u8>, AesError> {
+        pcbc_decrypt(self, ciphertext, iv)
+    }
+
+    /// CBC with ciphertext stealing: any length of at least 16 bytes, and the
+    /// ciphertext is exactly as long as the plaintext.
+    pub fn encrypt_cbc_cts(&self, plaintext: &[u8], iv: &[u8; 16], variant: CtsVariant) -> Result<Vec<u8>, AesError> {
+        if plaintext.len() < 16 {
+            return Err(AesError::InvalidLength("CBC-CS needs at least 16 bytes"));
+        }
+        let tail = match plaintext.len() % 16 {
+            0 => 16,
+            d => d,
+        };
+        // CBC over the zero-padded plaintext, then drop the padding bytes'
+        // worth from the penultimate ciphertext block
+        let mut padded = plaintext.to_vec();
+        padded.resize(plaintext.len().div_ceil(16) * 16, 0);
+        let mut ciphertext = self.encrypt_cbc(&padded, iv)?;
+        if ciphertext.len() == 16 {
+            return Ok(ciphertext);
+        }
+
+        let last = ciphertext.split_off(ciphertext.len() - 16);
+        let penultimate = ciphertext.split_off(ciphertext.len() - 16);
+        if variant.swaps_last_blocks(tail) {
+            ciphertext.extend_from_slice(&last);
+            ciphertext.extend_from_slice(&penultimate[..tail]);
+        } else {
+            ciphertext.extend_from_slice(&penultimate[..tail]);
+            ciphertext.extend_from_slice(&last);
+        }
+        Ok(ciphertext)
+    }
+
+    pub fn decrypt_cbc_cts(&self, ciphertext: &[u8], iv: &[u8; 16], variant: CtsVariant) -> Result<Vec<u8>, AesError> {
+        if ciphertext.len() < 16 {
+            return Err(AesError::InvalidLength("CBC-CS needs at least 16 bytes"));
+        }
+        if ciphertext.len() == 16 {
+            return self.decrypt_cbc(ciphertext, iv);
+        }
+        let tail = match ciphertext.len() % 16 {
+            0 => 16,
+            d => d,
+        };
+        let head_len = ciphertext.len() - 16 - tail;
+        let (head, rest) = ciphertext.split_at(head_len);
+        // back to CS1 order: C_{n-1}* (tail bytes) then C_n
+        let (stolen, last) = if variant.swaps_last_blocks(tail) { (&rest[16..], &rest[..16]) } else { (&rest[..tail], &rest[tail..]) };
+
+        // D(C_n) = C_{n-1} ^ (P_n* || 0): its trailing bytes complete C_{n-1}
+        let mut last_block = [0u8; 16];
+        last_block.copy_from_slice(last);
+        let z = self.decrypt_block(&last_block);
+        let mut penultimate = [0u8; 16];
+        penultimate[..tail].copy_from_slice(stolen);
+        penultimate[tail..].copy_from_slice(&z[tail..]);
+
+        let mut chained = head.to_vec();
+        chained.extend_from_slice(&penultimate);
+        let mut plaintext = self.decrypt_cbc(&chained, iv)?;
+        plaintext.extend(z[..tail].iter().zip(stolen.iter()).map(|(a, b)| a ^ b));
+        Ok(plaintext)
+    }
+}
+
+/// Check OFB and CFB against NIST SP 800-38A F.3/F.4 (AES-128), round-trip
+/// PCBC and show its block-swap weakness.
+pub fn _test_aes_modes() -> Result<(), Box<dyn Error>> {
+    let key = hex_to_bytes("2b7e151628aed2a6abf7158809cf4f3c")?;
+    let plaintext = hex_to_bytes(
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710",
+    )?;
+    let mut iv = [0u8; 16];
+    iv.copy_from_slice(&hex_to_bytes("000102030405060708090a0b0c0d0e0f")?);
+    let aes = AesStandard::new(&key)?;
+
+    let report = |name: &str, ct: &[u8], expected: &str, pt: &[u8], original: &[u8]| -> Result<(), Box<dyn Error>> {
+        let ok = ct == hex_to_bytes(expected)?.as_slice() && pt == original;
+        println!("{}: {}", name, if ok { "matches SP 800-38A" } else { "mismatch!" });
+        Ok(())
+    };
+
+    // F.4.1 OFB-AES128.Encrypt
+    let ct = aes.encrypt_ofb(&plaintext, &iv);
+    report("OFB", &ct, "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+                        9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e",
+           &aes.decrypt_ofb(&ct, &iv), &plaintext)?;
+
+    // F.3.13 CFB128-AES128.Encrypt
+    let ct = aes.encrypt_cfb128(&plaintext, &iv);
+    report("CFB-128", &ct, "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                            26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6",
+           &aes.decrypt_cfb128(&ct, &iv), &plaintext)?;
+
+    // F.3.7 CFB8-AES128.Encrypt (18 bytes)
+    let ct = aes.encrypt_cfb8(&plaintext[..18], &iv);
+    report("CFB-8", &ct, "3b79424c9c0dd436bace9e0ed4586a4f32b9",
+           &aes.decrypt_cfb8(&ct, &iv), &plaintext[..18])?;
+
+    // streaming modes on a length that is not a multiple of 16
+    let odd = &plaintext[..37];
+    let ok = aes.decrypt_ofb(&aes.encrypt_ofb(odd, &iv), &iv) == odd
+        && aes.decrypt_cfb128(&aes.encrypt_cfb128(odd, &iv), &iv) == odd;
+    println!("OFB/CFB-128 on 37 bytes: {}", if ok { "round-trip ok" } else { "mismatch!" });
+
+    // PCBC: round trip, then swap ciphertext blocks 1 and 2. Only those two
+    // plaintext blocks come out wrong and the last block still decrypts, so
+    // an integrity check on the final block alone misses the tampering.
+    let ct = aes.encrypt_pcbc(&plaintext, &iv)?;
+    let pt = aes.decrypt_pcbc(&ct, &iv)?;
+    println!("PCBC: {}", if pt == plaintext { "round-trip ok" } else { "mismatch!" });
+
+    let mut swapped = ct.clone();
+    swapped[16..32].copy_from_slice(&ct[32..48]);
+    swapped[32..48].copy_from_slice(&ct[16..32]);
+    let tampered = aes.decrypt_pcbc(&swapped, &iv)?;
+    let intact: Vec<bool> = (0..4)
+        .map(|i| tampered[i * 16..i * 16 + 16] == plaintext[i * 16..i * 16 + 16])
+        .collect();
+    println!("PCBC after swapping blocks 1 and 2, blocks intact: {:?}", intact);
+    Ok(())
+}
+
+/// CBC-CS3 against RFC 3962 (Kerberos AES-CTS, zero IV) and CS1/CS2 round trips.
+pub fn _test_cbc_cts() -> Result<(), Box<dyn Error>> {
+    let aes = AesStandard::new(&hex_to_bytes("636869636b656e207465726979616b69")?)?;
+    let iv = [0u8; 16];
+
+    // RFC 3962 Appendix B: "I would like the " and "I would like the General Gau's "
+    let vectors = [
+        ("4920776f756c64206c696b652074686520", "c6353568f2bf8cb4d8a580362da7ff7f97"),
+        (
+            "4920776f756c64206c696b65207468652047656e6572616c20476175277320",
+            "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+        ),
+    ];
+    for (pt, ct) in vectors {
+        let pt = hex_to_bytes(pt)?;
+        let encrypted = aes.encrypt_cbc_cts(&pt, &iv, CtsVariant::Cs3)?;
+        let ok = encrypted == hex_to_bytes(ct)? && aes.decrypt_cbc_cts(&encrypted, &iv, CtsVariant::Cs3)? == pt;
+        println!("CBC-CS3 {} bytes: {}", pt.len(), if ok { "matches RFC 3962" } else { "mismatch!" });
+    }
+
+    let message: Vec<u8> = (0..64u8).collect();
+    for variant in [CtsVariant::Cs1, CtsVariant::Cs2, CtsVariant::Cs3] {
+        let ok = [16, 17, 31, 32, 47, 64].iter().all(|&len| {
+            let encrypted = aes.encrypt_cbc_cts(&message[..len], &iv, variant);
+            let decrypted = encrypted.and_then(|ct| {
+                if ct.len() != len {
+                    return Err(AesError::InvalidLength("length changed"));
+                }
+                aes.decrypt_cbc_cts(&ct, &iv, variant)
+            });
+            matches!(decrypted, Ok(pt) if pt == message[..len])
+        });
+        println!("CBC-{:?} round trips: {}", variant, if ok { "ok" } else { "mismatch!" });
+    }
+    Ok(())
+}
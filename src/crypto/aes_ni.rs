@@ -0,0 +1,141 @@
+use zeroize::Zeroize;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{
+    __m128i, _mm_loadu_si128, _mm_storeu_si128, _mm_xor_si128,
+    _mm_aesenc_si128, _mm_aesenclast_si128, _mm_aesdec_si128, _mm_aesdeclast_si128, _mm_aesimc_si128,
+};
+
+// blocks kept in flight at once so the AESENC pipeline stays full
+const INTERLEAVE: usize = 8;
+const MAX_ROUND_KEYS: usize = 15;
+
+/// True when the CPU supports the AES-NI and SSE2 instructions (runtime check).
+pub fn is_supported() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        std::arch::is_x86_feature_detected!("aes") && std::arch::is_x86_feature_detected!("sse2")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// Round keys for the AES-NI backend.
+///
+/// Can only be built when `is_supported()` holds, so holding one is proof that
+/// the intrinsics below are safe to execute.
+#[derive(Clone)]
+pub struct AesNiKeys {
+    enc: Vec<[u8; 16]>,
+    // AESIMC-transformed keys for the equivalent inverse cipher, decryption order
+    dec: Vec<[u8; 16]>,
+}
+
+impl AesNiKeys {
+    pub fn new(expanded_key: &[u8], rounds: usize) -> Option<Self> {
+        if !is_supported() {
+            return None;
+        }
+        let enc: Vec<[u8; 16]> = expanded_key
+            .chunks_exact(16)
+            .take(rounds + 1)
+            .map(|rk| rk.try_into().unwrap())
+            .collect();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            // SAFETY: AES-NI support was checked above
+            let dec = unsafe { inverse_round_keys(&enc) };
+            Some(Self { enc, dec })
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            let _ = enc;
+            None
+        }
+    }
+}
+
+impl Drop for AesNiKeys {
+    fn drop(&mut self) {
+        self.enc.zeroize();
+        self.dec.zeroize();
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "aes,sse2")]
+unsafe fn inverse_round_keys(enc: &[[u8; 16]]) -> Vec<[u8; 16]> {
+    let last = enc.len() - 1;
+    let mut dec: Vec<[u8; 16]> = Vec::with_capacity(enc.len());
+    for (i, rk) in enc.iter().rev().enumerate() {
+        if i == 0 || i == last {
+            dec.push(*rk);
+        } else {
+            let mut out = [0u8; 16];
+            unsafe {
+                let k = _mm_loadu_si128(rk.as_ptr() as *const __m128i);
+                _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, _mm_aesimc_si128(k));
+            }
+            dec.push(out);
+        }
+    }
+    dec
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "aes,sse2")]
+unsafe fn crypt_blocks(round_keys: &[[u8; 16]], blocks: &mut [[u8; 16]], decrypt: bool) {
+    let rounds = round_keys.len() - 1;
+    let mut rk = [unsafe { _mm_loadu_si128(round_keys[0].as_ptr() as *const __m128i) }; MAX_ROUND_KEYS];
+    for (slot, key) in rk.iter_mut().zip(round_keys.iter()) {
+        *slot = unsafe { _mm_loadu_si128(key.as_ptr() as *const __m128i) };
+    }
+
+    for chunk in blocks.chunks_mut(INTERLEAVE) {
+        let mut state = [rk[0]; INTERLEAVE];
+        for (s, block) in state.iter_mut().zip(chunk.iter()) {
+            *s = _mm_xor_si128(unsafe { _mm_loadu_si128(block.as_ptr() as *const __m128i) }, rk[0]);
+        }
+        for key in &rk[1..rounds] {
+            for s in state[..chunk.len()].iter_mut() {
+                *s = if decrypt { _mm_aesdec_si128(*s, *key) } else { _mm_aesenc_si128(*s, *key) };
+            }
+        }
+        for (s, block) in state.iter().zip(chunk.iter_mut()) {
+            let out = if decrypt {
+                _mm_aesdeclast_si128(*s, rk[rounds])
+            } else {
+                _mm_aesenclast_si128(*s, rk[rounds])
+            };
+            unsafe { _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, out) };
+        }
+    }
+    rk.zeroize();
+}
+
+/// Encrypt blocks in place with AESENC, eight at a time.
+pub fn encrypt_blocks(keys: &AesNiKeys, blocks: &mut [[u8; 16]]) {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: `AesNiKeys` only exists when AES-NI was detected
+    unsafe { crypt_blocks(&keys.enc, blocks, false) };
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let _ = (keys, blocks);
+        unreachable!("AES-NI keys cannot be built on this target");
+    }
+}
+
+/// Decrypt blocks in place with AESDEC, eight at a time.
+pub fn decrypt_blocks(keys: &AesNiKeys, blocks: &mut [[u8; 16]]) {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: `AesNiKeys` only exists when AES-NI was detected
+    unsafe { crypt_blocks(&keys.dec, blocks, true) };
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let _ = (keys, blocks);
+        unreachable!("AES-NI keys cannot be built on this target");
+    }
+}
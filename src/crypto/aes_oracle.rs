@@ -1,16 +1,109 @@
-use rand::{Rng, random};
+use rand::{Rng, RngCore, SeedableRng, rngs::{OsRng, StdRng}};
 use std::collections::HashSet;
-use crate::crypto::aes_encyption::{AesStandard,  gen_key, pkcs7_padding};
-use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use crate::crypto::aes_encyption::{
+    AesStandard, AesError, gen_key_with_rng, random_bytes_with_rng, seeded_rng, pkcs7_padding, pkcs7_unpadding,
+};
+use crate::crypto::block_cipher::{BlockCipher, ecb_encrypt, cbc_encrypt};
+use crate::crypto::toy_cipher::ToyFeistel;
 
-thread_local! {
-    pub static KEY: RefCell<Option<Vec<u8>>> = RefCell::new(None);
-    pub static PREFIX: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+// Challenge 14's random prefix is 0..=MAX_PREFIX_LEN bytes
+const MAX_PREFIX_LEN: usize = 64;
+
+/// Key and random prefix behind the byte-at-a-time ECB oracles
+/// (Challenges 12 and 14).
+///
+/// Build it from a seed for reproducible runs or from the OS RNG; each
+/// context is independent, so several can live in one thread. `rekey()`
+/// draws a new key, `reset()` a new key and a new prefix.
+pub struct OracleContext {
+    rng: StdRng,
+    cipher: AesStandard,
+    prefix: Vec<u8>,
+}
+
+impl OracleContext {
+    /// Deterministic context: the same seed gives the same key and prefix.
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_rng(seeded_rng(seed))
+    }
+
+    /// Context keyed from OS randomness.
+    pub fn from_os_rng() -> Self {
+        Self::from_rng(&mut OsRng)
+    }
+
+    /// Context seeded from another RNG, e.g. one shared by a whole replayable run.
+    pub fn from_rng(rng: &mut impl RngCore) -> Self {
+        Self::with_rng(StdRng::from_rng(rng).expect("RNG failed"))
+    }
+
+    fn with_rng(mut rng: StdRng) -> Self {
+        let cipher = Self::new_cipher(&mut rng);
+        let prefix = Self::new_prefix(&mut rng);
+        Self { rng, cipher, prefix }
+    }
+
+    fn new_cipher(rng: &mut StdRng) -> AesStandard {
+        let key = gen_key_with_rng(16, rng);
+        AesStandard::new(&key).expect("16-byte key")
+    }
+
+    fn new_prefix(rng: &mut StdRng) -> Vec<u8> {
+        let mut prefix = vec![0u8; rng.gen_range(0..=MAX_PREFIX_LEN)];
+        rng.fill_bytes(&mut prefix);
+        prefix
+    }
+
+    /// Draw a fresh key; the prefix is kept.
+    pub fn rekey(&mut self) {
+        self.cipher = Self::new_cipher(&mut self.rng);
+    }
+
+    /// Draw a fresh key and a fresh prefix.
+    pub fn reset(&mut self) {
+        self.rekey();
+        self.prefix = Self::new_prefix(&mut self.rng);
+    }
+
+    /// The hidden prefix, for checking an attack's answer.
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix
+    }
+
+    /// Challenge 12: ECB(data || suffix).
+    pub fn encryption_oracle(&self, data: &[u8], suffix_bytes: &[u8]) -> Vec<u8> {
+        ecb_suffix_oracle(&self.cipher, data, suffix_bytes)
+    }
+
+    /// Challenge 14: ECB(random prefix || data || suffix).
+    pub fn prefix_oracle(&self, data: &[u8], suffix_bytes: &[u8]) -> Vec<u8> {
+        let mut input: Vec<u8> = self.prefix.clone();
+        input.extend_from_slice(data);
+        ecb_suffix_oracle(&self.cipher, &input, suffix_bytes)
+    }
 }
 /// Encryption oracle: randomly encrypts with ECB or CBC
-pub fn encryption_oracle_random(data: &[u8]) -> (Vec<u8>, &'static str) {
-    let key = gen_key(16);
-    let mut rng = rand::thread_rng();
+pub fn encryption_oracle_random(data: &[u8]) -> (Vec<u8>, CipherMode) {
+    encryption_oracle_random_with_rng(data, &mut OsRng)
+}
+
+/// Same as `encryption_oracle_random`, with the key and every choice drawn from `rng`.
+pub fn encryption_oracle_random_with_rng(data: &[u8], rng: &mut impl RngCore) -> (Vec<u8>, CipherMode) {
+    let key = gen_key_with_rng(16, rng);
+    let cipher = AesStandard::new(&key).unwrap();
+    encryption_oracle_random_with(&cipher, data, rng)
+}
+
+/// Same as `encryption_oracle_random` over any block cipher, keyed by the caller.
+pub fn encryption_oracle_random_with<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    rng: &mut impl RngCore,
+) -> (Vec<u8>, CipherMode) {
+    let block_size = cipher.block_size();
 
     // Add random prefix + suffix
     let prefix_len = rng.gen_range(5..=10);
@@ -18,46 +111,135 @@ pub fn encryption_oracle_random(data: &[u8]) -> (Vec<u8>, &'static str) {
     let mut input = Vec::with_capacity(prefix_len + data.len() + suffix_len);
 
     // prefix random bytes
-    for _ in 0..prefix_len { input.push(random::<u8>());}
+    input.extend(random_bytes_with_rng(prefix_len, rng));
     input.extend_from_slice(data);
     // suffix random bytes
-    for _ in 0..suffix_len {input.push(random::<u8>());}
+    input.extend(random_bytes_with_rng(suffix_len, rng));
 
     // Pad input
-    let padded = pkcs7_padding(&input, 16);
+    let padded = pkcs7_padding(&input, block_size).unwrap();
 
     // Pick ECB or CBC
-    let cipher = AesStandard::new(&key).unwrap();
     if rng.gen_bool(0.5) {
-        let ciphertext = cipher.encrypt_ecb(&padded).unwrap();
-        (ciphertext, "ECB")
+        let ciphertext = ecb_encrypt(cipher, &padded).unwrap();
+        (ciphertext, CipherMode::Ecb)
     } else {
-        let iv: [u8; 16] = gen_key(16).try_into().unwrap();
-        let ciphertext = cipher.encrypt_cbc(&padded, &iv).unwrap();
-        (ciphertext, "CBC")
+        let iv = random_bytes_with_rng(block_size, rng);
+        let ciphertext = cbc_encrypt(cipher, &padded, &iv).unwrap();
+        (ciphertext, CipherMode::Cbc)
+    }
+}
+
+/// Block cipher mode chosen by the Challenge 11 oracle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherMode {
+    Ecb,
+    Cbc,
+}
+
+impl CipherMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CipherMode::Ecb => "ECB",
+            CipherMode::Cbc => "CBC",
+        }
+    }
+}
+
+impl fmt::Display for CipherMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // pad so width/alignment flags work in tables
+        f.pad(self.as_str())
     }
 }
 
+/// Verdict of `detect_mode_scored`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModeDetection {
+    pub mode: CipherMode,
+    /// Posterior probability of `mode` with even prior odds, in 0.5..=1.
+    pub confidence: f64,
+    /// Ciphertext blocks equal to an earlier block.
+    pub repeated_blocks: usize,
+}
+
 /// Detect if ECB was used by checking for repeated 16-byte blocks
-pub fn detect_mode(ciphertext: &[u8]) -> &'static str {
+pub fn detect_mode(ciphertext: &[u8]) -> CipherMode {
+    detect_mode_with_block_size(ciphertext, 16)
+}
+
+/// Detect if ECB was used by checking for repeated blocks of `block_size` bytes
+pub fn detect_mode_with_block_size(ciphertext: &[u8], block_size: usize) -> CipherMode {
     let mut seen = HashSet::new();
-    for block in ciphertext.chunks(16) {
+    for block in ciphertext.chunks(block_size) {
         if !seen.insert(block) {
-            return "ECB";
+            return CipherMode::Ecb;
         }
     }
-    "CBC"
+    CipherMode::Cbc
+}
+
+/// ECB/CBC detection with a confidence value, for any block size.
+///
+/// `identical_blocks` is how many identical, block-aligned plaintext blocks the
+/// chosen input is guaranteed to produce (see `min_chosen_plaintext_len`);
+/// pass 0 if unknown. Under CBC a repeated block is a birthday collision, with
+/// probability about n(n-1)/2 / 2^(8 * block_size) for n blocks. Under ECB at
+/// least `identical_blocks - 1` repeats are certain, so with two or more
+/// guaranteed blocks a ciphertext without repeats is certainly CBC; with
+/// fewer, no repeats says nothing and the confidence stays at 0.5.
+pub fn detect_mode_scored(ciphertext: &[u8], block_size: usize, identical_blocks: usize) -> Result<ModeDetection, AesError> {
+    if block_size == 0 || ciphertext.is_empty() || !ciphertext.len().is_multiple_of(block_size) {
+        return Err(AesError::InvalidLength("Ciphertext must be a non-empty multiple of the block size"));
+    }
+    let blocks = ciphertext.len() / block_size;
+    let mut seen = HashSet::new();
+    let repeated_blocks = ciphertext.chunks_exact(block_size).filter(|block| !seen.insert(*block)).count();
+
+    let pairs = (blocks * (blocks - 1) / 2) as f64;
+    let cbc_collision = (pairs * 2f64.powi(-8 * block_size as i32)).min(1.0);
+    let detection = if repeated_blocks > 0 {
+        // a repeat is certain under ECB when guaranteed, and never less
+        // likely than under CBC otherwise
+        ModeDetection { mode: CipherMode::Ecb, confidence: 1.0 / (1.0 + cbc_collision), repeated_blocks }
+    } else if identical_blocks >= 2 {
+        ModeDetection { mode: CipherMode::Cbc, confidence: 1.0, repeated_blocks }
+    } else {
+        ModeDetection { mode: CipherMode::Cbc, confidence: 0.5, repeated_blocks }
+    };
+    Ok(detection)
+}
+
+/// Shortest run of identical chosen bytes that yields `identical_blocks`
+/// whole, aligned blocks for every prefix length in `prefix_len`.
+///
+/// A prefix of length p pushes the input start to p mod block_size, wasting
+/// up to block_size - 1 bytes before the first boundary; the worst p in the
+/// range decides. The random suffix comes after the input and does not
+/// matter. For Challenge 11 (5..=10 byte prefix, 16-byte blocks, 2 blocks)
+/// this is 43 bytes. Fails with `AesError::InvalidLength` if `block_size` is zero.
+pub fn min_chosen_plaintext_len(
+    block_size: usize,
+    prefix_len: RangeInclusive<usize>,
+    identical_blocks: usize,
+) -> Result<usize, AesError> {
+    if block_size == 0 {
+        return Err(AesError::InvalidLength("Block size must be nonzero"));
+    }
+    let worst_waste = prefix_len.map(|p| (block_size - p % block_size) % block_size).max().unwrap_or(0);
+    Ok(worst_waste + identical_blocks * block_size)
 }
 
 /// Confirms ECB mode by testing with repeating input blocks
 /// encryption_fn: The encryption oracle function to test
 /// suffix_bytes: The secret suffix bytes to append
 /// block_size: The detected block size to verify
+/// Fails with `AesError::NotEcb` if no adjacent blocks repeat.
 pub fn confirm_ecb(
     oracle: impl Fn(&[u8], Vec<u8>) -> Vec<u8>,
     suffix_bytes: Vec<u8>,
     block_size: usize
-) -> bool {
+) -> Result<(), AesError> {
     // Feed in a long string of 'A's (long enough to cover any prefix misalignment)
     let probe: Vec<u8> = vec![b'A'; block_size * 64];
     let ct: Vec<u8> = oracle(&probe, suffix_bytes);
@@ -70,13 +252,13 @@ pub fn confirm_ecb(
     }
 
     // Look for any repeated adjacent block
-    for i in 0..(blocks.len() - 1) {
+    for i in 0..blocks.len().saturating_sub(1) {
         if blocks[i] == blocks[i + 1] {
-            return true; // ECB confirmed
+            return Ok(()); // ECB confirmed
         }
     }
 
-    panic!("Not using ECB mode!");
+    Err(AesError::NotEcb)
 }
 
 /// Detects the block size by monitoring ciphertext length changes
@@ -140,29 +322,16 @@ pub fn find_next_byte(
     None // No match found (end of message or error)
 }
 
-/// Encryption oracle: Appends secret suffix and encrypts with ECB
-/// data: User-controlled input bytes
-/// suffix_bytes: Secret bytes to be decrypted
-pub fn encryption_oracle(data: &[u8], suffix_bytes: Vec<u8>) -> Vec<u8> {
-    KEY.with(|key_cell: &RefCell<Option<Vec<u8>>>| {
-        let mut key_opt: std::cell::RefMut<'_, Option<Vec<u8>>> = key_cell.borrow_mut();
+/// ECB(data || suffix) under any block cipher, PKCS#7 padded to its block size
+pub fn ecb_suffix_oracle<C: BlockCipher>(cipher: &C, data: &[u8], suffix_bytes: &[u8]) -> Vec<u8> {
+    // Combine user input with secret suffix
+    let mut plaintext: Vec<u8> = Vec::new();
+    plaintext.extend_from_slice(data);
+    plaintext.extend_from_slice(suffix_bytes);
 
-        // Generate key once and reuse it (thread-local storage)
-        if key_opt.is_none() {
-            *key_opt = Some(gen_key(16));
-        }
-        let key: &Vec<u8> = key_opt.as_ref().unwrap();
-
-        // Combine user input with secret suffix
-        let mut plaintext: Vec<u8> = Vec::new();
-        plaintext.extend_from_slice(data);
-        plaintext.extend_from_slice(&suffix_bytes);
-
-        // PKCS#7 pad to block size and encrypt with ECB
-        let padded: Vec<u8> = pkcs7_padding(&plaintext, 16);
-        let cipher: AesStandard = AesStandard::new(key).unwrap();
-        cipher.encrypt_ecb(&padded).unwrap()
-    })
+    // PKCS#7 pad to block size and encrypt with ECB
+    let padded: Vec<u8> = pkcs7_padding(&plaintext, cipher.block_size()).unwrap();
+    ecb_encrypt(cipher, &padded).unwrap()
 }
 
 
@@ -209,3 +378,124 @@ pub fn find_prefix_len(
     0
 }
 
+
+/// Seeded contexts are reproducible and independent, `rekey`/`reset` change
+/// what they should, and the Challenge 14 attack finds each hidden prefix.
+pub fn _test_oracle_context() -> Result<(), Box<dyn Error>> {
+    let secret = b"Rollin' in my 5.0, with my rag-top down".to_vec();
+    let (a, b, c) = (OracleContext::from_seed(7), OracleContext::from_seed(7), OracleContext::from_seed(8));
+    println!(
+        "Same seed, same ciphertext: {}; different seed, same ciphertext: {}",
+        a.prefix_oracle(b"input", &secret) == b.prefix_oracle(b"input", &secret),
+        a.prefix_oracle(b"input", &secret) == c.prefix_oracle(b"input", &secret),
+    );
+
+    let mut ctx = OracleContext::from_os_rng();
+    let before = ctx.encryption_oracle(b"input", &secret);
+    let prefix = ctx.prefix().to_vec();
+    ctx.rekey();
+    let rekeyed = ctx.encryption_oracle(b"input", &secret) != before && ctx.prefix() == prefix;
+    ctx.reset();
+    println!("rekey() keeps the prefix: {}, reset() replaces it: {}", rekeyed, ctx.prefix() != prefix);
+
+    // Challenge 14: find the prefix, then hide it behind an aligned oracle
+    // and run the Challenge 12 attack unchanged
+    let mut found = 0;
+    for seed in 0..16 {
+        let ctx = OracleContext::from_seed(seed);
+        let oracle = |data: &[u8], suffix: Vec<u8>| ctx.prefix_oracle(data, &suffix);
+        let block_size = find_block_size(oracle, secret.clone());
+        if find_prefix_len(oracle, secret.clone(), block_size) == ctx.prefix().len() {
+            found += 1;
+        }
+    }
+    println!("Prefix length found for {}/16 seeds", found);
+
+    let ctx = OracleContext::from_seed(2024);
+    let oracle = |data: &[u8], suffix: Vec<u8>| ctx.prefix_oracle(data, &suffix);
+    let block_size = find_block_size(oracle, secret.clone());
+    let prefix_len = find_prefix_len(oracle, secret.clone(), block_size);
+    let align = (block_size - prefix_len % block_size) % block_size;
+    let skip = prefix_len + align;
+    let aligned = |data: &[u8], suffix: Vec<u8>| {
+        let mut input = vec![b'A'; align];
+        input.extend_from_slice(data);
+        oracle(&input, suffix)[skip..].to_vec()
+    };
+    let mut recovered: Vec<u8> = Vec::new();
+    while let Some(byte) = find_next_byte(aligned, secret.clone(), &recovered, block_size) {
+        recovered.push(byte);
+    }
+    let recovered = pkcs7_unpadding(&recovered).unwrap_or(recovered);
+    println!(
+        "Challenge 14 with a {}-byte prefix: {}",
+        prefix_len,
+        if recovered == secret { "suffix recovered" } else { "mismatch!" },
+    );
+    Ok(())
+}
+
+/// Challenge 11 from a seed: two runs give identical ciphertexts and choices,
+/// so the detection rate is something a test can assert.
+pub fn _test_seeded_oracle() -> Result<(), Box<dyn Error>> {
+    let run = |seed: u64| -> Vec<(Vec<u8>, CipherMode)> {
+        let mut rng = seeded_rng(seed);
+        (0..64).map(|_| encryption_oracle_random_with_rng(&[b'A'; 64], &mut rng)).collect()
+    };
+    let first = run(11);
+    let detected = first.iter().filter(|(ct, actual)| detect_mode(ct) == *actual).count();
+    let ecb = first.iter().filter(|(_, actual)| *actual == CipherMode::Ecb).count();
+    println!(
+        "Seed 11: {} ECB / {} CBC, detected {}/64; replay identical: {}, seed 12 identical: {}",
+        ecb,
+        64 - ecb,
+        detected,
+        run(11) == first,
+        run(12) == first,
+    );
+    Ok(())
+}
+
+/// Scored detection with the minimum chosen input, one byte short of it,
+/// and on the 8-byte toy cipher; `confirm_ecb` and `min_chosen_plaintext_len`
+/// error instead of panicking.
+pub fn _test_mode_detection() -> Result<(), Box<dyn Error>> {
+    let mut rng = seeded_rng(47);
+    for block_size in [16, 8] {
+        let needed = min_chosen_plaintext_len(block_size, 5..=10, 2)?;
+        let toy = ToyFeistel::new(&gen_key_with_rng(8, &mut rng))?;
+        for len in [needed, needed - 1] {
+            let (mut correct, mut certain) = (0, 0);
+            for _ in 0..200 {
+                let (ct, actual) = if block_size == 16 {
+                    encryption_oracle_random_with_rng(&vec![b'A'; len], &mut rng)
+                } else {
+                    encryption_oracle_random_with(&toy, &vec![b'A'; len], &mut rng)
+                };
+                let guaranteed = if len == needed { 2 } else { 1 };
+                let detection = detect_mode_scored(&ct, block_size, guaranteed)?;
+                correct += usize::from(detection.mode == actual);
+                certain += usize::from(detection.confidence > 0.999);
+            }
+            println!(
+                "{}-byte blocks, {} chosen bytes: {}/200 correct, {}/200 with confidence > 0.999",
+                block_size, len, correct, certain,
+            );
+        }
+    }
+
+    let cbc_oracle = |data: &[u8], suffix: Vec<u8>| {
+        let aes = AesStandard::new(&gen_key_with_rng(16, &mut seeded_rng(1))).unwrap();
+        let padded = pkcs7_padding(&[data, &suffix].concat(), 16).unwrap();
+        cbc_encrypt(&aes, &padded, &[0u8; 16]).unwrap()
+    };
+    match confirm_ecb(cbc_oracle, b"suffix".to_vec(), 16) {
+        Err(e) => println!("confirm_ecb on a CBC oracle: {}", e),
+        Ok(()) => println!("confirm_ecb accepted a CBC oracle!"),
+    }
+    match min_chosen_plaintext_len(0, 5..=10, 2) {
+        Err(e) => println!("min_chosen_plaintext_len with 0-byte blocks: {}", e),
+        Ok(len) => return Err(format!("min_chosen_plaintext_len accepted 0-byte blocks ({})", len).into()),
+    }
+    Ok(())
+}
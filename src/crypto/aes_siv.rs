@@ -0,0 +1,133 @@
+use std::error::Error;
+use crate::crypto::aes_encyption::{AesStandard, AesError};
+use crate::crypto::cbc_mac::{aes_cmac, dbl};
+use crate::basics::encodings::hex_to_bytes;
+
+/// AES-SIV (RFC 5297): deterministic, nonce-misuse-resistant AEAD.
+///
+/// The key is split in half: K1 keys the S2V PRF (built from CMAC), K2 keys
+/// CTR mode. The synthetic IV V = S2V(AD_1, ..., AD_n, P) is both the tag and
+/// the CTR starting counter. For nonce-based use, pass the nonce as the last
+/// associated data component.
+pub struct AesSiv {
+    mac: AesStandard,
+    ctr: AesStandard,
+}
+
+impl AesSiv {
+    /// `key` is 32, 48 or 64 bytes (AES-SIV-256/384/512).
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        if ![32, 48, 64].contains(&key.len()) {
+            return Err(AesError::InvalidLength("SIV key must be 32, 48 or 64 bytes"));
+        }
+        let (mac_key, ctr_key) = key.split_at(key.len() / 2);
+        Ok(Self {
+            mac: AesStandard::new(mac_key)?,
+            ctr: AesStandard::new(ctr_key)?,
+        })
+    }
+
+    /// S2V over a vector of strings (RFC 5297, section 2.4).
+    pub fn s2v(&self, strings: &[&[u8]]) -> Result<[u8; 16], AesError> {
+        let cmac = |data: &[u8]| -> Result<u128, AesError> { Ok(u128::from_be_bytes(aes_cmac(&self.mac, data)?)) };
+
+        let Some((last, rest)) = strings.split_last() else {
+            return aes_cmac(&self.mac, &1u128.to_be_bytes());
+        };
+        let mut d = cmac(&[0u8; 16])?;
+        for s in rest {
+            d = dbl(d) ^ cmac(s)?;
+        }
+
+        let t: Vec<u8> = if last.len() >= 16 {
+            // xorend: D is XORed into the final 16 bytes
+            let mut t = last.to_vec();
+            let end = t.len() - 16;
+            for (b, k) in t[end..].iter_mut().zip(d.to_be_bytes().iter()) {
+                *b ^= k;
+            }
+            t
+        } else {
+            let mut padded = [0u8; 16];
+            padded[..last.len()].copy_from_slice(last);
+            padded[last.len()] = 0x80;
+            (dbl(d) ^ u128::from_be_bytes(padded)).to_be_bytes().to_vec()
+        };
+        aes_cmac(&self.mac, &t)
+    }
+
+    /// Returns V || C.
+    pub fn encrypt(&self, associated_data: &[&[u8]], plaintext: &[u8]) -> Result<Vec<u8>, AesError> {
+        let mut strings = associated_data.to_vec();
+        strings.push(plaintext);
+        let v = self.s2v(&strings)?;
+
+        let mut output = v.to_vec();
+        output.extend(self.ctr.encrypt_ctr(plaintext, &Self::counter(&v)));
+        Ok(output)
+    }
+
+    /// Decrypts V || C and recomputes V; fails with `AuthenticationFailed` on mismatch.
+    pub fn decrypt(&self, associated_data: &[&[u8]], ciphertext: &[u8]) -> Result<Vec<u8>, AesError> {
+        if ciphertext.len() < 16 {
+            return Err(AesError::InvalidLength("SIV ciphertext must include the 16-byte V"));
+        }
+        let (v, body) = ciphertext.split_at(16);
+        let mut v_block = [0u8; 16];
+        v_block.copy_from_slice(v);
+        let plaintext = self.ctr.decrypt_ctr(body, &Self::counter(&v_block));
+
+        let mut strings = associated_data.to_vec();
+        strings.push(&plaintext);
+        let expected = self.s2v(&strings)?;
+
+        // compare every byte so the check does not stop early
+        let diff = expected.iter().zip(v.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(AesError::AuthenticationFailed);
+        }
+        Ok(plaintext)
+    }
+
+    // Q = V with bits 31 and 63 cleared, so implementations may use 32-bit counters
+    fn counter(v: &[u8; 16]) -> [u8; 16] {
+        let mut q = *v;
+        q[8] &= 0x7f;
+        q[12] &= 0x7f;
+        q
+    }
+}
+
+/// RFC 5297 appendix A vectors; the same input always gives the same output.
+pub fn _test_aes_siv() -> Result<(), Box<dyn Error>> {
+    // A.1 deterministic authenticated encryption
+    let siv = AesSiv::new(&hex_to_bytes("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")?)?;
+    let ad = hex_to_bytes("101112131415161718191a1b1c1d1e1f2021222324252627")?;
+    let pt = hex_to_bytes("112233445566778899aabbccddee")?;
+    let ct = siv.encrypt(&[&ad], &pt)?;
+    let ok = ct == hex_to_bytes("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c")?
+        && siv.decrypt(&[&ad], &ct)? == pt;
+    println!("SIV A.1: {}", if ok { "matches RFC 5297" } else { "mismatch!" });
+
+    // A.2 nonce-based, two AD components and a nonce
+    let siv = AesSiv::new(&hex_to_bytes("7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f")?)?;
+    let ad1 = hex_to_bytes("00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100")?;
+    let ad2 = hex_to_bytes("102030405060708090a0")?;
+    let nonce = hex_to_bytes("09f911029d74e35bd84156c5635688c0")?;
+    let pt = b"this is some plaintext to encrypt using SIV-AES";
+    let ct = siv.encrypt(&[&ad1, &ad2, &nonce], pt)?;
+    let expected = hex_to_bytes(
+        "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17\
+         dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d",
+    )?;
+    let ok = ct == expected && siv.decrypt(&[&ad1, &ad2, &nonce], &ct)? == pt;
+    println!("SIV A.2: {}", if ok { "matches RFC 5297" } else { "mismatch!" });
+
+    let mut tampered = ct.clone();
+    tampered[20] ^= 1;
+    match siv.decrypt(&[&ad1, &ad2, &nonce], &tampered) {
+        Err(AesError::AuthenticationFailed) => println!("SIV rejects tampered ciphertext"),
+        _ => println!("SIV accepted a tampered ciphertext!"),
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use zeroize::Zeroize;
+use crate::crypto::aes_constants::{SBOX, INV_SBOX, TE0, TE1, TE2, TE3, TD0, TD1, TD2, TD3};
+
+/// Round keys in the word layout used by the T-table rounds.
+///
+/// `enc` is the expanded key as big-endian column words; `dec` holds the
+/// equivalent-inverse-cipher keys (InvMixColumns applied to rounds 1..Nr-1),
+/// stored in decryption order.
+#[derive(Clone)]
+pub struct TTableKeys {
+    enc: Vec<u32>,
+    dec: Vec<u32>,
+}
+
+impl TTableKeys {
+    pub fn new(expanded_key: &[u8], rounds: usize) -> Self {
+        let enc: Vec<u32> = expanded_key
+            .chunks_exact(4)
+            .map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+
+        let mut dec: Vec<u32> = Vec::with_capacity(enc.len());
+        for round in (0..=rounds).rev() {
+            for &w in &enc[round * 4..round * 4 + 4] {
+                if round == 0 || round == rounds {
+                    dec.push(w);
+                } else {
+                    dec.push(inv_mix_column_word(w));
+                }
+            }
+        }
+        Self { enc, dec }
+    }
+}
+
+impl Drop for TTableKeys {
+    fn drop(&mut self) {
+        self.enc.zeroize();
+        self.dec.zeroize();
+    }
+}
+
+// InvMixColumns of one column, reusing TD* (TD0[SBOX[b]] is b * (14, 9, 13, 11))
+fn inv_mix_column_word(w: u32) -> u32 {
+    TD0[SBOX[(w >> 24) as usize] as usize]
+        ^ TD1[SBOX[((w >> 16) & 0xff) as usize] as usize]
+        ^ TD2[SBOX[((w >> 8) & 0xff) as usize] as usize]
+        ^ TD3[SBOX[(w & 0xff) as usize] as usize]
+}
+
+fn load_state(input: &[u8; 16]) -> [u32; 4] {
+    let mut s = [0u32; 4];
+    for (c, word) in s.iter_mut().enumerate() {
+        *word = u32::from_be_bytes([input[4 * c], input[4 * c + 1], input[4 * c + 2], input[4 * c + 3]]);
+    }
+    s
+}
+
+fn store_state(s: &[u32; 4]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for (c, word) in s.iter().enumerate() {
+        out[4 * c..4 * c + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+#[inline(always)]
+fn byte(w: u32, n: u32) -> usize {
+    ((w >> (24 - 8 * n)) & 0xff) as usize
+}
+
+/// One block of AES encryption: SubBytes, ShiftRows and MixColumns are fused
+/// into four table lookups per column.
+pub fn encrypt_block(keys: &TTableKeys, rounds: usize, input: &[u8; 16]) -> [u8; 16] {
+    let rk = &keys.enc;
+    let mut s = load_state(input);
+    for c in 0..4 {
+        s[c] ^= rk[c];
+    }
+
+    for round in 1..rounds {
+        let k = &rk[round * 4..round * 4 + 4];
+        let mut t = [0u32; 4];
+        for c in 0..4 {
+            t[c] = TE0[byte(s[c], 0)]
+                ^ TE1[byte(s[(c + 1) % 4], 1)]
+                ^ TE2[byte(s[(c + 2) % 4], 2)]
+                ^ TE3[byte(s[(c + 3) % 4], 3)]
+                ^ k[c];
+        }
+        s = t;
+    }
+
+    // final round: SubBytes + ShiftRows only
+    let k = &rk[rounds * 4..rounds * 4 + 4];
+    let mut t = [0u32; 4];
+    for c in 0..4 {
+        t[c] = ((SBOX[byte(s[c], 0)] as u32) << 24)
+            | ((SBOX[byte(s[(c + 1) % 4], 1)] as u32) << 16)
+            | ((SBOX[byte(s[(c + 2) % 4], 2)] as u32) << 8)
+            | (SBOX[byte(s[(c + 3) % 4], 3)] as u32);
+        t[c] ^= k[c];
+    }
+    store_state(&t)
+}
+
+/// One block of AES decryption using the equivalent inverse cipher.
+pub fn decrypt_block(keys: &TTableKeys, rounds: usize, input: &[u8; 16]) -> [u8; 16] {
+    let dk = &keys.dec;
+    let mut s = load_state(input);
+    for c in 0..4 {
+        s[c] ^= dk[c];
+    }
+
+    for round in 1..rounds {
+        let k = &dk[round * 4..round * 4 + 4];
+        let mut t = [0u32; 4];
+        for c in 0..4 {
+            t[c] = TD0[byte(s[c], 0)]
+                ^ TD1[byte(s[(c + 3) % 4], 1)]
+                ^ TD2[byte(s[(c + 2) % 4], 2)]
+                ^ TD3[byte(s[(c + 1) % 4], 3)]
+                ^ k[c];
+        }
+        s = t;
+    }
+
+    let k = &dk[rounds * 4..rounds * 4 + 4];
+    let mut t = [0u32; 4];
+    for c in 0..4 {
+        t[c] = ((INV_SBOX[byte(s[c], 0)] as u32) << 24)
+            | ((INV_SBOX[byte(s[(c + 3) % 4], 1)] as u32) << 16)
+            | ((INV_SBOX[byte(s[(c + 2) % 4], 2)] as u32) << 8)
+            | (INV_SBOX[byte(s[(c + 1) % 4], 3)] as u32);
+        t[c] ^= k[c];
+    }
+    store_state(&t)
+}
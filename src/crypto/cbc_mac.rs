@@ -0,0 +1,199 @@
+use std::error::Error;
+use crate::crypto::aes_encyption::{AesStandard, AesError, pkcs7_padding, gen_key, random_bytes};
+use crate::basics::encodings::hex_to_bytes;
+
+/// CBC-MAC with a caller-supplied IV: PKCS#7 pad, CBC-encrypt, keep the last block.
+/// Letting the sender choose the IV is what makes the first-block forgery possible.
+pub fn cbc_mac(aes: &AesStandard, message: &[u8], iv: &[u8; 16]) -> Result<[u8; 16], AesError> {
+    let padded: Vec<u8> = pkcs7_padding(message, 16)?;
+    let ciphertext: Vec<u8> = aes.encrypt_cbc(&padded, iv)?;
+    Ok(last_block(&ciphertext))
+}
+
+/// CBC-MAC with a fixed all-zero IV.
+pub fn cbc_mac_zero_iv(aes: &AesStandard, message: &[u8]) -> Result<[u8; 16], AesError> {
+    cbc_mac(aes, message, &[0u8; 16])
+}
+
+/// AES-CMAC (RFC 4493).
+pub fn aes_cmac(aes: &AesStandard, message: &[u8]) -> Result<[u8; 16], AesError> {
+    let (k1, k2) = cmac_subkeys(aes);
+
+    let complete = !message.is_empty() && message.len().is_multiple_of(16);
+    let mut padded: Vec<u8> = message.to_vec();
+    let subkey = if complete {
+        k1
+    } else {
+        // 10* padding
+        padded.push(0x80);
+        while !padded.len().is_multiple_of(16) {
+            padded.push(0x00);
+        }
+        k2
+    };
+
+    let last = padded.len() - 16;
+    for (b, k) in padded[last..].iter_mut().zip(subkey.iter()) {
+        *b ^= k;
+    }
+    let ciphertext: Vec<u8> = aes.encrypt_cbc(&padded, &[0u8; 16])?;
+    Ok(last_block(&ciphertext))
+}
+
+/// Doubling in GF(2^128) with the CMAC polynomial x^128 + x^7 + x^2 + x + 1.
+pub(crate) fn dbl(block: u128) -> u128 {
+    let carry = block >> 127;
+    (block << 1) ^ (carry * 0x87)
+}
+
+/// CMAC subkeys K1, K2 derived from L = E_K(0^128) (RFC 4493, section 2.3).
+pub fn cmac_subkeys(aes: &AesStandard) -> ([u8; 16], [u8; 16]) {
+    let l = u128::from_be_bytes(aes.encrypt_block(&[0u8; 16]));
+    let k1 = dbl(l);
+    let k2 = dbl(k1);
+    (k1.to_be_bytes(), k2.to_be_bytes())
+}
+
+fn last_block(bytes: &[u8]) -> [u8; 16] {
+    let mut block = [0u8; 16];
+    block.copy_from_slice(&bytes[bytes.len() - 16..]);
+    block
+}
+
+// ----------------------------
+// Attacks
+// ----------------------------
+
+/// Attacker-controlled IV forgery.
+///
+/// The first CBC input block is `IV ^ P_0`, so a message that differs only in its
+/// first 16 bytes keeps the same MAC if the IV absorbs the difference:
+/// `IV' = IV ^ P_0 ^ P'_0`. Returns the IV to send alongside `forged`.
+pub fn forge_cbc_mac_iv(original: &[u8], iv: &[u8; 16], forged: &[u8]) -> Result<[u8; 16], AesError> {
+    if original.len() != forged.len() || original.len() < 16 {
+        return Err(AesError::InvalidLength("Forged message must match original length (at least 16 bytes)"));
+    }
+    if original[16..] != forged[16..] {
+        return Err(AesError::InvalidLength("Only the first block may differ"));
+    }
+
+    let mut new_iv: [u8; 16] = *iv;
+    for i in 0..16 {
+        new_iv[i] ^= original[i] ^ forged[i];
+    }
+    Ok(new_iv)
+}
+
+/// Zero-IV length extension by message concatenation.
+///
+/// Given `(first, first_tag)` and `(second, second_tag)` under the same key, builds
+/// `pad(first) || (second_0 ^ first_tag) || second[16..]` whose MAC is `second_tag`:
+/// after `pad(first)` the chaining value is `first_tag`, which the glue block cancels.
+pub fn cbc_mac_length_extension(
+    first: &[u8],
+    first_tag: &[u8; 16],
+    second: &[u8],
+    second_tag: &[u8; 16],
+) -> Result<(Vec<u8>, [u8; 16]), AesError> {
+    if second.len() < 16 {
+        return Err(AesError::InvalidLength("Extension message must be at least 16 bytes"));
+    }
+
+    let mut extended: Vec<u8> = pkcs7_padding(first, 16)?;
+    extended.extend(second[..16].iter().zip(first_tag.iter()).map(|(a, b)| a ^ b));
+    extended.extend_from_slice(&second[16..]);
+    Ok((extended, *second_tag))
+}
+
+/// CBC-MAC used as a hash with a known key: forge a JavaScript snippet that runs
+/// `payload` but hashes to the same value as `original`.
+///
+/// Layout: `payload // filler` aligned to a block, then one glue block chosen so
+/// the chaining value matches `original`'s first block, then `original[16..]`.
+/// The glue block sits inside the line comment, so the filler is varied until it
+/// contains no line terminator.
+pub fn forge_cbc_mac_hash_collision(key: &[u8], original: &[u8], payload: &[u8]) -> Result<Vec<u8>, AesError> {
+    if original.len() < 16 {
+        return Err(AesError::InvalidLength("Original snippet must be at least 16 bytes"));
+    }
+    let aes = AesStandard::new(key)?;
+
+    for filler in b'a'..=b'z' {
+        let mut prefix: Vec<u8> = payload.to_vec();
+        prefix.extend_from_slice(b"//");
+        // at least one filler byte, or every attempt would build the same prefix
+        prefix.push(filler);
+        while !prefix.len().is_multiple_of(16) {
+            prefix.push(filler);
+        }
+
+        // chaining value after the (unpadded) prefix
+        let state: [u8; 16] = last_block(&aes.encrypt_cbc(&prefix, &[0u8; 16])?);
+        let glue: Vec<u8> = state.iter().zip(original[..16].iter()).map(|(s, o)| s ^ o).collect();
+        if glue.iter().any(|&b| b == b'\n' || b == b'\r') {
+            continue;
+        }
+
+        let mut forged: Vec<u8> = prefix;
+        forged.extend_from_slice(&glue);
+        forged.extend_from_slice(&original[16..]);
+        return Ok(forged);
+    }
+    Err(AesError::InvalidLength("No newline-free glue block found"))
+}
+
+pub fn _test_cbc_mac() -> Result<(), Box<dyn Error>> {
+    // RFC 4493, section 4 examples
+    let aes = AesStandard::new(&hex_to_bytes("2b7e151628aed2a6abf7158809cf4f3c")?)?;
+    let message = hex_to_bytes(
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+         30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710",
+    )?;
+    let vectors: [(usize, &str); 4] = [
+        (0, "bb1d6929e95937287fa37d129b756746"),
+        (16, "070a16b46b4d4144f79bdd9dd04a287c"),
+        (40, "dfa66747de9ae63030ca32611497c827"),
+        (64, "51f0bebf7e3b9d92fc49741779363cfe"),
+    ];
+    for (len, expected) in vectors {
+        let tag = aes_cmac(&aes, &message[..len])?;
+        if tag.to_vec() == hex_to_bytes(expected)? {
+            println!("CMAC matches for {}-byte message", len);
+        } else {
+            println!("CMAC mismatch for {}-byte message!", len);
+        }
+    }
+
+    // IV forgery: move the source account of a signed transfer
+    let bank = AesStandard::new(&gen_key(16))?;
+    let iv: [u8; 16] = random_bytes(16).try_into().unwrap();
+    let original: &[u8] = b"from=0002&to=0002&amount=1000000";
+    let forged: &[u8] = b"from=0001&to=0002&amount=1000000";
+    let tag = cbc_mac(&bank, original, &iv)?;
+    let forged_iv = forge_cbc_mac_iv(original, &iv, forged)?;
+    println!("IV forgery valid: {}", cbc_mac(&bank, forged, &forged_iv)? == tag);
+
+    // Length extension with a zero IV
+    let victim: &[u8] = b"from=0001&tx_list=0003:5000";
+    let own: &[u8] = b";0002:1000000;0002:1000000";
+    let (extended, ext_tag) = cbc_mac_length_extension(
+        victim, &cbc_mac_zero_iv(&bank, victim)?, own, &cbc_mac_zero_iv(&bank, own)?,
+    )?;
+    println!("Length extension valid: {}", cbc_mac_zero_iv(&bank, &extended)? == ext_tag);
+
+    // JavaScript hash collision (key is public)
+    let key: &[u8] = b"YELLOW SUBMARINE";
+    let snippet: &[u8] = b"alert('MZA who was that?');\n";
+    let hasher = AesStandard::new(key)?;
+    let target = cbc_mac_zero_iv(&hasher, snippet)?;
+    println!("Original hash: {}", hex::encode(target));
+
+    let collision = forge_cbc_mac_hash_collision(key, snippet, b"alert('Ayo, the Wu is back!');")?;
+    let forged_hash = cbc_mac_zero_iv(&hasher, &collision)?;
+    println!("Forged hash:   {} (match: {})", hex::encode(forged_hash), forged_hash == target);
+
+    // 14-byte payload: "//" alone would end exactly on a block boundary
+    let collision = forge_cbc_mac_hash_collision(key, snippet, b"alert('pwn');;")?;
+    println!("14-byte payload forged hash match: {}", cbc_mac_zero_iv(&hasher, &collision)? == target);
+    Ok(())
+}
@@ -0,0 +1,399 @@
+use std::error::Error;
+use std::fmt;
+
+/// Pair separator of a `k=v` cookie string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CookieFormat {
+    /// `k=v&k=v` (Challenge 13 profiles).
+    #[default]
+    Ampersand,
+    /// `k=v;k=v` (Challenge 16 comment strings).
+    Semicolon,
+}
+
+impl CookieFormat {
+    pub fn pair_sep(self) -> u8 {
+        match self {
+            CookieFormat::Ampersand => b'&',
+            CookieFormat::Semicolon => b';',
+        }
+    }
+
+    // The other format's separator: never legitimate unescaped in a value
+    fn foreign_sep(self) -> u8 {
+        match self {
+            CookieFormat::Ampersand => b';',
+            CookieFormat::Semicolon => b'&',
+        }
+    }
+}
+
+/// How keys and values are made safe when encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Escaping {
+    /// `%XX` for `%`, `&`, `;`, `=` and anything outside printable ASCII;
+    /// decoded again when parsing.
+    #[default]
+    Percent,
+    /// Drop the format's pair separator and `=` and pass every other byte
+    /// through, as the Challenge 13 service does.
+    Strip,
+}
+
+/// What to do when a key appears more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the first value.
+    First,
+    /// Keep the last value, at the position of the first occurrence.
+    #[default]
+    Last,
+    /// Fail with `CookieError::DuplicateKey`.
+    Reject,
+}
+
+/// Why a cookie string was rejected. Offsets are byte positions in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieError {
+    /// Two separators in a row, or one at either end.
+    EmptyPair { offset: usize },
+    MissingValueSeparator { offset: usize },
+    EmptyKey { offset: usize },
+    /// An unescaped `=` inside a value, or the other format's pair separator.
+    SmuggledSeparator { offset: usize, byte: u8 },
+    /// `%` not followed by two hex digits.
+    BadEscape { offset: usize },
+    /// A control or non-ASCII byte that an honest encoder would have escaped.
+    RawByte { offset: usize, byte: u8 },
+    /// A key that does not decode to UTF-8; values may hold any bytes.
+    InvalidUtf8 { offset: usize },
+    DuplicateKey(String),
+}
+
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieError::EmptyPair { offset } => write!(f, "Empty pair at byte {}", offset),
+            CookieError::MissingValueSeparator { offset } => write!(f, "Pair at byte {} has no '='", offset),
+            CookieError::EmptyKey { offset } => write!(f, "Pair at byte {} has an empty key", offset),
+            CookieError::SmuggledSeparator { offset, byte } => {
+                write!(f, "Unescaped '{}' at byte {}", *byte as char, offset)
+            }
+            CookieError::BadEscape { offset } => write!(f, "Malformed percent escape at byte {}", offset),
+            CookieError::RawByte { offset, byte } => write!(f, "Raw byte 0x{:02x} at byte {}", byte, offset),
+            CookieError::InvalidUtf8 { offset } => write!(f, "Key at byte {} is not valid UTF-8", offset),
+            CookieError::DuplicateKey(key) => write!(f, "Duplicate key '{}'", key),
+        }
+    }
+}
+
+impl Error for CookieError {}
+
+/// Ordered `k=v` pairs; encoding writes them back in the same order.
+///
+/// Keys are text, values are raw bytes: whatever the caller supplied,
+/// `&str` or `&[u8]`, is what gets encoded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pairs: Vec<(String, Vec<u8>)>,
+}
+
+impl Cookie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a pair, even if the key is already present.
+    pub fn push(&mut self, key: &str, value: impl AsRef<[u8]>) {
+        self.pairs.push((key.to_string(), value.as_ref().to_vec()));
+    }
+
+    /// Builder form of `push`.
+    pub fn with(mut self, key: &str, value: impl AsRef<[u8]>) -> Self {
+        self.push(key, value);
+        self
+    }
+
+    /// Replace the first value for `key`, or append the pair.
+    pub fn set(&mut self, key: &str, value: impl AsRef<[u8]>) {
+        match self.pairs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.as_ref().to_vec(),
+            None => self.push(key, value),
+        }
+    }
+
+    /// First value for `key`.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_slice())
+    }
+
+    /// First value for `key`, if it is valid UTF-8.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|v| std::str::from_utf8(v).ok())
+    }
+
+    pub fn pairs(&self) -> &[(String, Vec<u8>)] {
+        &self.pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+/// Encoder and parsers for one cookie format.
+///
+/// `parse` is what a forgiving service does: it skips empty pairs, keeps
+/// malformed escapes literally and decodes non-UTF-8 keys lossily, so it
+/// still reads `admin=true` out of a plaintext with a scrambled block.
+/// `parse_strict` rejects anything an honest `encode` could not have
+/// produced, which is how smuggled separators show up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CookieCodec {
+    format: CookieFormat,
+    escaping: Escaping,
+    duplicates: DuplicatePolicy,
+}
+
+impl CookieCodec {
+    /// Percent escaping, last value wins on duplicates.
+    pub fn new(format: CookieFormat) -> Self {
+        Self { format, ..Self::default() }
+    }
+
+    pub fn with_escaping(mut self, escaping: Escaping) -> Self {
+        self.escaping = escaping;
+        self
+    }
+
+    pub fn with_duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn format(&self) -> CookieFormat {
+        self.format
+    }
+
+    /// Key or value made safe to embed between separators.
+    pub fn escape(&self, text: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(text.len());
+        for &b in text {
+            match self.escaping {
+                Escaping::Strip if b == self.format.pair_sep() || b == b'=' => {}
+                Escaping::Strip => out.push(b),
+                Escaping::Percent if b == b'%' || is_separator(b) || !is_printable(b) => {
+                    out.extend_from_slice(format!("%{:02X}", b).as_bytes());
+                }
+                Escaping::Percent => out.push(b),
+            }
+        }
+        out
+    }
+
+    /// All pairs, escaped and joined in order.
+    pub fn encode(&self, cookie: &Cookie) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, (k, v)) in cookie.pairs.iter().enumerate() {
+            if i > 0 {
+                out.push(self.format.pair_sep());
+            }
+            out.extend(self.escape(k.as_bytes()));
+            out.push(b'=');
+            out.extend(self.escape(v));
+        }
+        out
+    }
+
+    /// Forgiving parse; only the duplicate policy can make it fail.
+    pub fn parse(&self, input: &[u8]) -> Result<Cookie, CookieError> {
+        let mut cookie = Cookie::new();
+        for (_, pair) in self.split_pairs(input) {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = match pair.iter().position(|&b| b == b'=') {
+                Some(eq) => (&pair[..eq], &pair[eq + 1..]),
+                None => (pair, &[][..]),
+            };
+            let key = String::from_utf8_lossy(&self.unescape_lossy(key)).into_owned();
+            let value = self.unescape_lossy(value);
+            self.insert(&mut cookie, key, value, self.duplicates)?;
+        }
+        Ok(cookie)
+    }
+
+    /// Parse that fails on the first sign of tampering: empty pairs or
+    /// keys, a missing or extra `=`, the other format's separator, bad
+    /// escapes, raw control or non-ASCII bytes, keys that are not UTF-8,
+    /// and any duplicate key whatever the codec's policy.
+    pub fn parse_strict(&self, input: &[u8]) -> Result<Cookie, CookieError> {
+        let mut cookie = Cookie::new();
+        for (offset, pair) in self.split_pairs(input) {
+            if pair.is_empty() {
+                return Err(CookieError::EmptyPair { offset });
+            }
+            for (i, &b) in pair.iter().enumerate() {
+                if !is_printable(b) {
+                    return Err(CookieError::RawByte { offset: offset + i, byte: b });
+                }
+                if b == self.format.foreign_sep() {
+                    return Err(CookieError::SmuggledSeparator { offset: offset + i, byte: b });
+                }
+            }
+            let eq = pair.iter().position(|&b| b == b'=')
+                .ok_or(CookieError::MissingValueSeparator { offset })?;
+            if eq == 0 {
+                return Err(CookieError::EmptyKey { offset });
+            }
+            if let Some(i) = pair[eq + 1..].iter().position(|&b| b == b'=') {
+                return Err(CookieError::SmuggledSeparator { offset: offset + eq + 1 + i, byte: b'=' });
+            }
+
+            let key = self.unescape_strict(&pair[..eq], offset)?;
+            let value = self.unescape_strict(&pair[eq + 1..], offset + eq + 1)?;
+            let key = String::from_utf8(key).map_err(|_| CookieError::InvalidUtf8 { offset })?;
+            self.insert(&mut cookie, key, value, DuplicatePolicy::Reject)?;
+        }
+        Ok(cookie)
+    }
+
+    // Pairs with their starting offsets, including empty ones
+    fn split_pairs<'a>(&self, input: &'a [u8]) -> Vec<(usize, &'a [u8])> {
+        let mut offset = 0;
+        input
+            .split(|&b| b == self.format.pair_sep())
+            .map(|pair| {
+                let start = offset;
+                offset += pair.len() + 1;
+                (start, pair)
+            })
+            .collect()
+    }
+
+    fn insert(&self, cookie: &mut Cookie, key: String, value: Vec<u8>, policy: DuplicatePolicy) -> Result<(), CookieError> {
+        match (cookie.pairs.iter_mut().find(|(k, _)| *k == key), policy) {
+            (None, _) => cookie.pairs.push((key, value)),
+            (Some(_), DuplicatePolicy::First) => {}
+            (Some((_, v)), DuplicatePolicy::Last) => *v = value,
+            (Some(_), DuplicatePolicy::Reject) => return Err(CookieError::DuplicateKey(key)),
+        }
+        Ok(())
+    }
+
+    // Decode valid escapes, keep anything else as it is
+    fn unescape_lossy(&self, text: &[u8]) -> Vec<u8> {
+        if self.escaping == Escaping::Strip {
+            return text.to_vec();
+        }
+        let mut out = Vec::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len() {
+            match decode_escape(&text[i..]) {
+                Some(b) => {
+                    out.push(b);
+                    i += 3;
+                }
+                None => {
+                    out.push(text[i]);
+                    i += 1;
+                }
+            }
+        }
+        out
+    }
+
+    fn unescape_strict(&self, text: &[u8], offset: usize) -> Result<Vec<u8>, CookieError> {
+        if self.escaping == Escaping::Strip {
+            return Ok(text.to_vec());
+        }
+        let mut out = Vec::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len() {
+            if text[i] == b'%' {
+                out.push(decode_escape(&text[i..]).ok_or(CookieError::BadEscape { offset: offset + i })?);
+                i += 3;
+            } else {
+                out.push(text[i]);
+                i += 1;
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn is_separator(b: u8) -> bool {
+    matches!(b, b'&' | b';' | b'=')
+}
+
+fn is_printable(b: u8) -> bool {
+    (0x21..=0x7e).contains(&b)
+}
+
+// `%XX` at the start of `text`
+fn decode_escape(text: &[u8]) -> Option<u8> {
+    if text.len() < 3 || text[0] != b'%' || !text[1..3].iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let hex = std::str::from_utf8(&text[1..3]).ok()?;
+    u8::from_str_radix(hex, 16).ok()
+}
+
+/// Round trips in both formats, duplicate policies, the Challenge 13
+/// stripping encoder, and what the strict parser catches.
+pub fn _test_cookie() -> Result<(), Box<dyn Error>> {
+    let cookie = Cookie::new()
+        .with("comment1", "cooking MCs")
+        .with("userdata", ";admin=true;")
+        .with("note", "100% & more");
+    for format in [CookieFormat::Ampersand, CookieFormat::Semicolon] {
+        let codec = CookieCodec::new(format);
+        let encoded = codec.encode(&cookie);
+        println!(
+            "{:?}: {} -> strict round trip ok: {}",
+            format, String::from_utf8_lossy(&encoded), codec.parse_strict(&encoded)? == cookie,
+        );
+    }
+
+    let input = b"role=user&uid=10&role=admin";
+    for policy in [DuplicatePolicy::First, DuplicatePolicy::Last, DuplicatePolicy::Reject] {
+        let parsed = CookieCodec::new(CookieFormat::Ampersand).with_duplicates(policy).parse(input);
+        println!("{:?}: {:?}", policy, parsed.map(|c| c.get_str("role").map(str::to_string)));
+    }
+
+    let profiles = CookieCodec::new(CookieFormat::Ampersand).with_escaping(Escaping::Strip);
+    let profile = Cookie::new().with("email", "foo@bar.com&role=admin;x").with("uid", "10").with("role", "user");
+    println!("Stripped: {}", String::from_utf8_lossy(&profiles.encode(&profile)));
+
+    // values are bytes: nothing is lost to UTF-8 on the way through
+    let raw = Cookie::new().with("email", b"\xff\xfe@bar.com").with("uid", "10");
+    let percent = CookieCodec::new(CookieFormat::Ampersand);
+    let encoded = percent.encode(&raw);
+    println!(
+        "Raw bytes: {} -> strict round trip ok: {}, stripped length {}",
+        String::from_utf8_lossy(&encoded), percent.parse_strict(&encoded)? == raw, profiles.encode(&raw).len(),
+    );
+
+    let semicolon = CookieCodec::new(CookieFormat::Semicolon);
+    let tampered: [&[u8]; 7] = [
+        b"userdata=x;admin=true=1",
+        b"userdata=x&admin=true;comment2=y",
+        b"userdata=x;;admin=true",
+        b"userdata=%zz;admin=true",
+        b"userdata=\x8f\x13;admin=true",
+        b"userdata=x;admin",
+        b"admin=false;admin=true",
+    ];
+    for input in tampered {
+        let lenient = semicolon.parse(input).map(|c| c.get("admin").map(|v| String::from_utf8_lossy(v).into_owned()));
+        match semicolon.parse_strict(input) {
+            Err(e) => println!("{:<36} lenient admin={:?}, strict: {}", format!("{:?}", String::from_utf8_lossy(input)), lenient, e),
+            Ok(_) => println!("{:?} accepted by the strict parser!", String::from_utf8_lossy(input)),
+        }
+    }
+    Ok(())
+}
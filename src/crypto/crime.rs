@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::fmt;
+use rand::{RngCore, SeedableRng, rngs::{OsRng, StdRng}};
+use crate::crypto::aes_encyption::{AesStandard, gen_key_with_rng, random_bytes_with_rng, seeded_rng, pkcs7_padding};
+use crate::crypto::deflate::deflate;
+
+// Base64 session ids, plus the newline that ends the cookie header
+const SESSION_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/=\n";
+
+// Filler for the CBC alignment: distinct 8-bit literals (0x80..=0x8f) that
+// never occur in an ASCII request, so DEFLATE can only emit them as literals
+const FILLER_START: u8 = 0x80;
+const MAX_FILLER: usize = 16;
+
+// Bit-offset shifts: 9-bit literals (0x90..=0xff), SHIFTS of them at most
+const SHIFT_START: u8 = 0xa0;
+const SHIFTS: usize = 8;
+
+// Breaks the match in the second of the two tries; flips to "\x7f~}|"
+const GAP: &[u8] = &[0xff, 0xfe, 0xfd, 0xfc];
+
+/// How `CompressionOracle` encrypts the compressed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrimeMode {
+    /// Length leaks the compressed size to the byte.
+    Ctr,
+    /// Length is rounded up to 16 bytes; the attack has to align first.
+    Cbc,
+}
+
+impl fmt::Display for CrimeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            CrimeMode::Ctr => "CTR",
+            CrimeMode::Cbc => "CBC",
+        })
+    }
+}
+
+/// Compress-then-encrypt service for the CRIME attack (Challenge 51).
+///
+/// Each query DEFLATEs `attacker_data || secret` and encrypts it under AES
+/// with a fresh nonce or IV; the attacker only sees how long the result is.
+pub struct CompressionOracle {
+    rng: StdRng,
+    cipher: AesStandard,
+    mode: CrimeMode,
+    secret: Vec<u8>,
+}
+
+impl CompressionOracle {
+    /// Oracle keyed from the OS RNG.
+    pub fn new(mode: CrimeMode, secret: &[u8]) -> Self {
+        Self::from_rng(mode, secret, &mut OsRng)
+    }
+
+    /// Oracle keyed from `rng`; reproducible when `rng` is seeded.
+    pub fn from_rng(mode: CrimeMode, secret: &[u8], rng: &mut impl RngCore) -> Self {
+        let mut rng = StdRng::from_rng(rng).expect("RNG failed");
+        let key = gen_key_with_rng(16, &mut rng);
+        let cipher = AesStandard::new(&key).expect("16-byte key");
+        Self { rng, cipher, mode, secret: secret.to_vec() }
+    }
+
+    pub fn mode(&self) -> CrimeMode {
+        self.mode
+    }
+
+    /// Nonce or IV followed by the ciphertext of the compressed request.
+    pub fn encrypt(&mut self, attacker_data: &[u8]) -> Vec<u8> {
+        let mut request = attacker_data.to_vec();
+        request.extend_from_slice(&self.secret);
+        let compressed = deflate(&request);
+
+        let mut iv = [0u8; 16];
+        self.rng.fill_bytes(&mut iv);
+        let ciphertext = match self.mode {
+            CrimeMode::Ctr => {
+                // 8-byte nonce, 8-byte block counter
+                iv[8..].fill(0);
+                self.cipher.encrypt_ctr(&compressed, &iv)
+            }
+            CrimeMode::Cbc => {
+                let padded = pkcs7_padding(&compressed, 16).expect("16 is a valid block size");
+                self.cipher.encrypt_cbc(&padded, &iv).expect("padded to 16 bytes")
+            }
+        };
+        let mut out = iv.to_vec();
+        out.extend(ciphertext);
+        out
+    }
+
+    /// The only thing the attacker learns from a query.
+    pub fn ciphertext_len(&mut self, attacker_data: &[u8]) -> usize {
+        self.encrypt(attacker_data).len()
+    }
+}
+
+/// What `crime_attack` recovered and what it cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrimeResult {
+    /// Bytes recovered after the known prefix, without the terminator.
+    pub recovered: Vec<u8>,
+    /// Length step seen when the input grows: 1 for CTR, 16 for CBC.
+    pub granularity: usize,
+    pub queries: usize,
+    /// Positions where the best candidates still tied after lookahead.
+    pub ambiguous: usize,
+}
+
+/// Recover the secret that follows `known_prefix` (e.g. `sessionid=`) one
+/// byte at a time, using only ciphertext lengths. The secret and the
+/// alphabet must be ASCII.
+///
+/// Each candidate `c` is scored with "two tries": `guess || c || gap || t || gap`
+/// against `guess || gap || t || c || gap`, where `t` is the last two bytes of the
+/// guess. Both hold the same bytes and the same 3-byte runs ending in `c`,
+/// so only extending the whole guess's match against the secret makes the
+/// first one shorter. The same pair is
+/// also sent with the top bit of every guess byte flipped, which keeps any
+/// repeats inside the guess but can never match the secret; subtracting it
+/// cancels a guess that merely compresses against itself.
+///
+/// Under CBC the length only moves in whole blocks, so each measurement is
+/// aligned first: the number of incompressible filler bytes that still fit
+/// in the last block gives the compressed size to the byte. Bit offsets are
+/// averaged out by repeating everything behind a few 9-bit literals.
+///
+/// Ties are broken by looking one byte further ahead. Stops at
+/// `terminator`, after `max_len` bytes, or when no candidate wins; a secret
+/// that repeats 3-byte runs of itself can end the search early.
+pub fn crime_attack(
+    mut oracle: impl FnMut(&[u8]) -> usize,
+    known_prefix: &[u8],
+    alphabet: &[u8],
+    terminator: u8,
+    max_len: usize,
+) -> CrimeResult {
+    let mut queries = 0;
+    let mut query = |data: &[u8]| {
+        queries += 1;
+        oracle(data)
+    };
+    let granularity = length_granularity(&mut query);
+
+    let mut guess = known_prefix.to_vec();
+    let mut ambiguous = 0;
+    while guess.len() - known_prefix.len() < max_len {
+        let mut scores: Vec<(isize, u8)> = alphabet
+            .iter()
+            .map(|&c| (score(&mut query, &guess, &[c], granularity), c))
+            .collect();
+        let best = scores.iter().map(|&(s, _)| s).min().unwrap_or(0);
+        if best >= 0 {
+            break; // no candidate extends the match
+        }
+        scores.retain(|&(s, _)| s == best);
+
+        if scores.len() > 1 {
+            // score each tied byte by its best continuation
+            let tied: Vec<u8> = scores.iter().map(|&(_, c)| c).collect();
+            scores = tied
+                .iter()
+                .map(|&c| {
+                    let best_next = alphabet
+                        .iter()
+                        .map(|&d| score(&mut query, &guess, &[c, d], granularity))
+                        .min()
+                        .unwrap_or(0);
+                    (best_next, c)
+                })
+                .collect();
+            let best = scores.iter().map(|&(s, _)| s).min().unwrap_or(0);
+            scores.retain(|&(s, _)| s == best);
+            if scores.len() > 1 {
+                ambiguous += 1;
+            }
+        }
+
+        let next = scores[0].1;
+        if next == terminator {
+            break;
+        }
+        guess.push(next);
+    }
+
+    CrimeResult {
+        recovered: guess[known_prefix.len()..].to_vec(),
+        granularity,
+        queries,
+        ambiguous,
+    }
+}
+
+/// `crime_attack` against base64 session ids ending in a newline.
+pub fn recover_session_id(oracle: impl FnMut(&[u8]) -> usize, known_prefix: &[u8]) -> CrimeResult {
+    crime_attack(oracle, known_prefix, SESSION_ALPHABET, b'\n', 64)
+}
+
+// Smallest change in ciphertext length as filler is added
+fn length_granularity(query: &mut impl FnMut(&[u8]) -> usize) -> usize {
+    let mut step = usize::MAX;
+    let mut last = query(&[]);
+    for n in 1..=MAX_FILLER {
+        let len = query(&filler(n));
+        if len > last {
+            step = step.min(len - last);
+        }
+        last = len;
+    }
+    step
+}
+
+// Two-tries difference for `guess || extra`, minus the same with the top bit
+// flipped; negative when `extra` extends a match against the secret
+fn score(query: &mut impl FnMut(&[u8]) -> usize, guess: &[u8], extra: &[u8], granularity: usize) -> isize {
+    let flip = |bytes: &[u8]| -> Vec<u8> { bytes.iter().map(|b| b ^ 0x80).collect() };
+    two_tries(query, guess, extra, granularity) - two_tries(query, &flip(guess), &flip(extra), granularity)
+}
+
+fn two_tries(query: &mut impl FnMut(&[u8]) -> usize, guess: &[u8], extra: &[u8], granularity: usize) -> isize {
+    // the last two guess bytes go with `extra` in both tries, so a 3-byte
+    // match elsewhere in the secret helps both equally; the closing gap keeps
+    // them from running into the secret
+    let context = &guess[guess.len().saturating_sub(2)..];
+    (0..SHIFTS)
+        .map(|k| {
+            // k 9-bit literals move the bit offset by k
+            let shift: Vec<u8> = (0..k).map(|i| SHIFT_START + i as u8).collect();
+            let joined = [&shift[..], guess, extra, GAP, context, GAP].concat();
+            let split = [&shift[..], guess, GAP, context, extra, GAP].concat();
+            aligned_len(query, &joined, granularity) as isize - aligned_len(query, &split, granularity) as isize
+        })
+        .sum()
+}
+
+// Compressed size of `data` to within a byte: the ciphertext length minus
+// the filler that still fits in its last block. Each filler byte is an
+// 8-bit literal, so the length is monotonic in the filler count.
+fn aligned_len(query: &mut impl FnMut(&[u8]) -> usize, data: &[u8], granularity: usize) -> usize {
+    let base = query(data);
+    if granularity == 1 {
+        return base;
+    }
+    // smallest n in 1..=granularity that grows the ciphertext
+    let (mut lo, mut hi) = (1, granularity);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if query(&[&filler(mid)[..], data].concat()) > base {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    base - lo
+}
+
+fn filler(n: usize) -> Vec<u8> {
+    (0..n).map(|i| FILLER_START + i as u8).collect()
+}
+
+/// Recovers random base64 session ids through the CTR and CBC oracles, and
+/// shows what the length leak looks like for a right and a wrong guess.
+pub fn _test_crime() -> Result<(), Box<dyn Error>> {
+    let mut rng = seeded_rng(50);
+    for mode in [CrimeMode::Ctr, CrimeMode::Cbc] {
+        for _ in 0..2 {
+            let session_id: Vec<u8> = random_bytes_with_rng(32, &mut rng)
+                .iter()
+                .map(|b| SESSION_ALPHABET[(b % 64) as usize])
+                .collect();
+            let mut secret = b"\nHost: hapless.com\nCookie: sessionid=".to_vec();
+            secret.extend_from_slice(&session_id);
+            secret.extend_from_slice(b"\nContent-Length: 0\n");
+
+            let mut oracle = CompressionOracle::from_rng(mode, &secret, &mut rng);
+            let right = [&b"sessionid="[..], &session_id[..1]].concat();
+            println!(
+                "{}: {:?} -> {} bytes, 'sessionid=#' -> {} bytes",
+                mode, String::from_utf8_lossy(&right),
+                oracle.ciphertext_len(&right), oracle.ciphertext_len(b"sessionid=#"),
+            );
+
+            let result = recover_session_id(|data| oracle.ciphertext_len(data), b"sessionid=");
+            println!(
+                "{}: recovered {} in {} queries (length step {}, {} ambiguous), correct: {}",
+                mode, String::from_utf8_lossy(&result.recovered), result.queries,
+                result.granularity, result.ambiguous, result.recovered == session_id,
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use std::error::Error;
+use std::fmt;
+use crate::crypto::aes_encyption::{AesStandard, gen_key_with_rng, seeded_rng, pkcs7_padding, pkcs7_unpadding};
+use crate::crypto::block_cipher::{BlockCipher, ecb_encrypt, ecb_decrypt};
+use crate::crypto::toy_cipher::ToyFeistel;
+
+// Longest block size the planner will probe for
+const MAX_BLOCK_SIZE: usize = 255;
+
+/// Why a cut-and-paste forgery could not be planned or assembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgeError {
+    /// The template has no input marker.
+    MarkerNotFound,
+    /// No `field=` pair in the template.
+    FieldNotFound(String),
+    /// The field sits before the attacker input, so its value cannot be
+    /// pushed to a block boundary.
+    FieldBeforeInput(String),
+    /// A byte the planner must send would be stripped by the oracle.
+    ForbiddenByte(u8),
+    /// The ciphertext length never changed while the input grew.
+    BlockSizeNotFound,
+    /// Block size outside 1..=255, which PKCS#7 cannot pad to.
+    InvalidBlockSize(usize),
+    /// The oracle output does not match the template it was said to wrap.
+    TemplateMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for ForgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForgeError::MarkerNotFound => write!(f, "Template has no input marker"),
+            ForgeError::FieldNotFound(field) => write!(f, "Field '{}' not found in template", field),
+            ForgeError::FieldBeforeInput(field) => write!(f, "Field '{}' comes before the attacker input", field),
+            ForgeError::ForbiddenByte(b) => write!(f, "Payload needs byte 0x{:02x}, which the oracle strips", b),
+            ForgeError::BlockSizeNotFound => write!(f, "Could not detect block size"),
+            ForgeError::InvalidBlockSize(size) => write!(f, "Block size {} is not in 1..=255", size),
+            ForgeError::TemplateMismatch { expected, actual } => write!(
+                f, "Oracle returned {} bytes, template predicts {}", actual, expected,
+            ),
+        }
+    }
+}
+
+impl Error for ForgeError {}
+
+/// A `k=v` template encrypted under ECB with PKCS#7 padding, with the
+/// attacker's input spliced in at one point
+/// (Challenge 13: `email=<input>&uid=10&role=user`).
+#[derive(Debug, Clone)]
+pub struct CutAndPaste {
+    before: Vec<u8>,
+    after: Vec<u8>,
+    pair_sep: u8,
+    kv_sep: u8,
+    forbidden: Vec<u8>,
+    filler: u8,
+}
+
+/// The inputs to send and the blocks to splice for one forgery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgePlan {
+    pub block_size: usize,
+    /// Input that puts the end of `field=` on a block boundary.
+    pub align_input: Vec<u8>,
+    /// Leading blocks of the aligned ciphertext to keep.
+    pub keep_blocks: usize,
+    /// Input that puts the padded target value in blocks of its own.
+    pub value_input: Vec<u8>,
+    /// Index of the first value block in that ciphertext.
+    pub value_block: usize,
+    pub value_blocks: usize,
+    /// What the forged ciphertext decrypts to (unpadded).
+    pub forged_plaintext: Vec<u8>,
+    /// Template bytes after the overwritten value, lost in the forgery.
+    pub dropped: Vec<u8>,
+}
+
+impl CutAndPaste {
+    /// `template` with `marker` where the attacker input goes, e.g.
+    /// `b"email={}&uid=10&role=user"` and `b"{}"`. Defaults to `&`-separated
+    /// `k=v` pairs, an oracle that strips `&` and `=`, and `A` as filler.
+    pub fn new(template: &[u8], marker: &[u8]) -> Result<Self, ForgeError> {
+        let at = find(template, marker).ok_or(ForgeError::MarkerNotFound)?;
+        Ok(Self {
+            before: template[..at].to_vec(),
+            after: template[at + marker.len()..].to_vec(),
+            pair_sep: b'&',
+            kv_sep: b'=',
+            forbidden: b"&=".to_vec(),
+            filler: b'A',
+        })
+    }
+
+    /// Pair and key/value separators; both are added to the forbidden bytes.
+    pub fn with_separators(mut self, pair_sep: u8, kv_sep: u8) -> Self {
+        self.pair_sep = pair_sep;
+        self.kv_sep = kv_sep;
+        self.forbidden.extend([pair_sep, kv_sep]);
+        self
+    }
+
+    /// More bytes the oracle strips or escapes from the input, on top of
+    /// the separators.
+    pub fn with_forbidden(mut self, forbidden: &[u8]) -> Self {
+        self.forbidden.extend_from_slice(forbidden);
+        self
+    }
+
+    /// Byte used to pad the input to a block boundary.
+    pub fn with_filler(mut self, filler: u8) -> Self {
+        self.filler = filler;
+        self
+    }
+
+    /// Work out the two queries and the splice that set `field` to `value`.
+    ///
+    /// Only fields after the input can be forged; pairs following the
+    /// overwritten value are cut off and listed in `dropped`.
+    pub fn plan(&self, field: &[u8], value: &[u8], block_size: usize) -> Result<ForgePlan, ForgeError> {
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+            return Err(ForgeError::InvalidBlockSize(block_size));
+        }
+        let name = String::from_utf8_lossy(field).into_owned();
+        let value_start = match self.value_offset(&self.after, field) {
+            Some(offset) => offset,
+            None if self.value_offset(&self.before, field).is_some() => {
+                return Err(ForgeError::FieldBeforeInput(name));
+            }
+            None => return Err(ForgeError::FieldNotFound(name)),
+        };
+        let value_end = self.after[value_start..]
+            .iter()
+            .position(|&b| b == self.pair_sep)
+            .map_or(self.after.len(), |i| value_start + i);
+
+        // Query 1: end `field=` exactly on a block boundary
+        let align_len = (block_size - (self.before.len() + value_start) % block_size) % block_size;
+        let align_input = vec![self.filler; align_len];
+        let keep_blocks = (self.before.len() + align_len + value_start) / block_size;
+
+        // Query 2: start the input on a block boundary, then the padded value
+        let lead_len = (block_size - self.before.len() % block_size) % block_size;
+        let padded_value = pkcs7_padding(value, block_size).map_err(|_| ForgeError::InvalidBlockSize(block_size))?;
+        let mut value_input = vec![self.filler; lead_len];
+        value_input.extend_from_slice(&padded_value);
+        if let Some(&b) = value_input.iter().find(|b| self.forbidden.contains(b)) {
+            return Err(ForgeError::ForbiddenByte(b));
+        }
+
+        let mut forged_plaintext = self.before.clone();
+        forged_plaintext.extend_from_slice(&align_input);
+        forged_plaintext.extend_from_slice(&self.after[..value_start]);
+        forged_plaintext.extend_from_slice(value);
+
+        Ok(ForgePlan {
+            block_size,
+            align_input,
+            keep_blocks,
+            value_block: (self.before.len() + lead_len) / block_size,
+            value_blocks: padded_value.len() / block_size,
+            value_input,
+            forged_plaintext,
+            dropped: self.after[value_end..].to_vec(),
+        })
+    }
+
+    /// Detect the block size, plan, query `oracle` and splice the forged
+    /// ciphertext that decrypts to `plan.forged_plaintext`.
+    pub fn forge(
+        &self,
+        oracle: impl Fn(&[u8]) -> Vec<u8>,
+        field: &[u8],
+        value: &[u8],
+    ) -> Result<(Vec<u8>, ForgePlan), ForgeError> {
+        let block_size = self.detect_block_size(&oracle)?;
+        let plan = self.plan(field, value, block_size)?;
+
+        let aligned = self.query(&oracle, &plan.align_input, block_size)?;
+        let chosen = self.query(&oracle, &plan.value_input, block_size)?;
+
+        let mut forged = aligned[..plan.keep_blocks * block_size].to_vec();
+        let start = plan.value_block * block_size;
+        forged.extend_from_slice(&chosen[start..start + plan.value_blocks * block_size]);
+        Ok((forged, plan))
+    }
+
+    // Smallest jump in ciphertext length as filler input grows
+    fn detect_block_size(&self, oracle: &impl Fn(&[u8]) -> Vec<u8>) -> Result<usize, ForgeError> {
+        if self.forbidden.contains(&self.filler) {
+            return Err(ForgeError::ForbiddenByte(self.filler));
+        }
+        let initial_len = oracle(&[]).len();
+        for n in 1..=MAX_BLOCK_SIZE {
+            let len = oracle(&vec![self.filler; n]).len();
+            if len != initial_len {
+                return Ok(len - initial_len);
+            }
+        }
+        Err(ForgeError::BlockSizeNotFound)
+    }
+
+    // Oracle output, checked against the length the template predicts
+    fn query(&self, oracle: &impl Fn(&[u8]) -> Vec<u8>, input: &[u8], block_size: usize) -> Result<Vec<u8>, ForgeError> {
+        let ct = oracle(input);
+        let plain_len = self.before.len() + input.len() + self.after.len();
+        let expected = (plain_len / block_size + 1) * block_size;
+        if ct.len() != expected {
+            return Err(ForgeError::TemplateMismatch { expected, actual: ct.len() });
+        }
+        Ok(ct)
+    }
+
+    // Offset just past `field=` in `part`, if the key starts a pair there
+    fn value_offset(&self, part: &[u8], field: &[u8]) -> Option<usize> {
+        let mut key = field.to_vec();
+        key.push(self.kv_sep);
+        (0..part.len()).find_map(|i| {
+            let starts_pair = i == 0 || part[i - 1] == self.pair_sep;
+            (starts_pair && part[i..].starts_with(&key)).then_some(i + key.len())
+        })
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Forges role=admin for the Challenge 13 layout under AES, a field in the
+/// middle of a `;`-separated template under the 8-byte toy cipher, and
+/// shows the planner's refusals.
+pub fn _test_cut_and_paste() -> Result<(), Box<dyn Error>> {
+    let mut rng = seeded_rng(48);
+
+    fn ecb_template<C: BlockCipher>(cipher: &C, before: &[u8], input: &[u8], after: &[u8], strip: &[u8]) -> Vec<u8> {
+        let mut pt = before.to_vec();
+        pt.extend(input.iter().filter(|b| !strip.contains(b)));
+        pt.extend_from_slice(after);
+        ecb_encrypt(cipher, &pkcs7_padding(&pt, cipher.block_size()).unwrap()).unwrap()
+    }
+
+    let aes = AesStandard::new(&gen_key_with_rng(16, &mut rng))?;
+    let planner = CutAndPaste::new(b"email={}&uid=10&role=user", b"{}")?;
+    let oracle = |input: &[u8]| ecb_template(&aes, b"email=", input, b"&uid=10&role=user", b"&=");
+    let (forged, plan) = planner.forge(oracle, b"role", b"admin")?;
+    let decrypted = pkcs7_unpadding(&ecb_decrypt(&aes, &forged)?)?;
+    println!(
+        "AES: sent {} + {} bytes, decrypts to {:?}, as planned: {}",
+        plan.align_input.len(), plan.value_input.len(),
+        String::from_utf8_lossy(&decrypted), decrypted == plan.forged_plaintext,
+    );
+
+    let toy = ToyFeistel::new(&gen_key_with_rng(8, &mut rng))?;
+    let planner = CutAndPaste::new(b"comment1=cooking;userdata=<>;role=user;uid=10", b"<>")?
+        .with_separators(b';', b'=');
+    let oracle = |input: &[u8]| ecb_template(&toy, b"comment1=cooking;userdata=", input, b";role=user;uid=10", b";=");
+    let (forged, plan) = planner.forge(oracle, b"role", b"admin")?;
+    let decrypted = pkcs7_unpadding(&ecb_decrypt(&toy, &forged)?)?;
+    println!(
+        "Toy: decrypts to {:?}, as planned: {}, dropped {:?}",
+        String::from_utf8_lossy(&decrypted), decrypted == plan.forged_plaintext,
+        String::from_utf8_lossy(&plan.dropped),
+    );
+
+    let planner = CutAndPaste::new(b"role=user&email={}&uid=10", b"{}")?;
+    for (field, value) in [(&b"role"[..], &b"admin"[..]), (b"name", b"x"), (b"uid", b"0&role=admin")] {
+        match planner.plan(field, value, 16) {
+            Err(e) => println!("Refused {}={}: {}", String::from_utf8_lossy(field), String::from_utf8_lossy(value), e),
+            Ok(plan) => println!("Planned {:?}", String::from_utf8_lossy(&plan.forged_plaintext)),
+        }
+    }
+
+    // extra forbidden bytes keep the separators forbidden, whatever the builder order
+    let planner = CutAndPaste::new(b"role=user;email={};uid=10", b"{}")?
+        .with_separators(b';', b'=')
+        .with_forbidden(b"&");
+    match planner.plan(b"uid", b"0;role=admin", 16) {
+        Err(e) => println!("Refused uid=0;role=admin: {}", e),
+        Ok(plan) => println!("Planned {:?}", String::from_utf8_lossy(&plan.forged_plaintext)),
+    }
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fmt;
+
+// DEFLATE (RFC 1951) limits
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 256;
+const HASH_BITS: u32 = 12;
+
+// Length codes 257..=285: base length and extra bits
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// Distance codes 0..=29: base distance and extra bits
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Why `inflate` rejected a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeflateError {
+    UnexpectedEof,
+    /// Dynamic-Huffman blocks (BTYPE 2) are not decoded here.
+    UnsupportedBlockType(u8),
+    InvalidSymbol(u16),
+    InvalidDistance(usize),
+    StoredLengthMismatch,
+}
+
+impl fmt::Display for DeflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeflateError::UnexpectedEof => write!(f, "DEFLATE stream ended early"),
+            DeflateError::UnsupportedBlockType(t) => write!(f, "Unsupported DEFLATE block type {}", t),
+            DeflateError::InvalidSymbol(s) => write!(f, "Invalid DEFLATE symbol {}", s),
+            DeflateError::InvalidDistance(d) => write!(f, "Distance {} reaches before the start", d),
+            DeflateError::StoredLengthMismatch => write!(f, "Stored block LEN/NLEN mismatch"),
+        }
+    }
+}
+
+impl Error for DeflateError {}
+
+/// One LZ77 token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(u8),
+    Match { len: usize, dist: usize },
+}
+
+/// Raw DEFLATE (RFC 1951): greedy longest-match LZ77 over a 32 KiB window,
+/// coded as a single block with the fixed Huffman tables.
+///
+/// Fixed codes give every literal a known cost (8 bits for 0..=143, 9 for
+/// 144..=255), so the output length depends only on what LZ77 could match.
+/// That makes it a clean model of a compression side channel; real zlib
+/// output with dynamic Huffman tables leaks the same thing, with more noise.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    out.write_bits(1, 1); // BFINAL
+    out.write_bits(1, 2); // BTYPE = fixed Huffman
+
+    for token in lz77(data) {
+        match token {
+            Token::Literal(b) => write_fixed_symbol(&mut out, b as u16),
+            Token::Match { len, dist } => {
+                let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap();
+                write_fixed_symbol(&mut out, 257 + code as u16);
+                out.write_bits((len - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code]);
+
+                let code = DIST_BASE.iter().rposition(|&base| base as usize <= dist).unwrap();
+                out.write_huffman(code as u32, 5);
+                out.write_bits((dist - DIST_BASE[code] as usize) as u32, DIST_EXTRA[code]);
+            }
+        }
+    }
+    write_fixed_symbol(&mut out, 256); // end of block
+    out.finish()
+}
+
+/// Decode a raw DEFLATE stream made of stored and fixed-Huffman blocks,
+/// such as `deflate` produces.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    let mut input = BitReader { data, pos: 0 };
+    let mut out: Vec<u8> = Vec::new();
+    loop {
+        let last = input.read_bits(1)? == 1;
+        match input.read_bits(2)? as u8 {
+            0 => {
+                input.align();
+                let len = input.read_bits(16)? as u16;
+                let nlen = input.read_bits(16)? as u16;
+                if len != !nlen {
+                    return Err(DeflateError::StoredLengthMismatch);
+                }
+                for _ in 0..len {
+                    out.push(input.read_bits(8)? as u8);
+                }
+            }
+            1 => loop {
+                let symbol = read_fixed_symbol(&mut input)?;
+                match symbol {
+                    0..=255 => out.push(symbol as u8),
+                    256 => break,
+                    257..=285 => {
+                        let code = (symbol - 257) as usize;
+                        let len = LENGTH_BASE[code] as usize + input.read_bits(LENGTH_EXTRA[code])? as usize;
+                        let code = input.read_huffman(5)? as usize;
+                        if code >= DIST_BASE.len() {
+                            return Err(DeflateError::InvalidSymbol(code as u16));
+                        }
+                        let dist = DIST_BASE[code] as usize + input.read_bits(DIST_EXTRA[code])? as usize;
+                        if dist > out.len() {
+                            return Err(DeflateError::InvalidDistance(dist));
+                        }
+                        // byte by byte: a match may overlap its own output
+                        for _ in 0..len {
+                            out.push(out[out.len() - dist]);
+                        }
+                    }
+                    _ => return Err(DeflateError::InvalidSymbol(symbol)),
+                }
+            },
+            btype => return Err(DeflateError::UnsupportedBlockType(btype)),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+// Greedy parse: at each position take the longest earlier match (ties go
+// to the nearest), or a literal if nothing reaches MIN_MATCH
+fn lz77(data: &[u8]) -> Vec<Token> {
+    // zlib-style hash chains over 3-byte prefixes: `head` holds the newest
+    // position for each hash, `prev` links each position to the one before
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let hash = |pos: usize| {
+        let key = (data[pos] as usize) << 16 | (data[pos + 1] as usize) << 8 | data[pos + 2] as usize;
+        (key.wrapping_mul(0x9e37_79b1) >> 7) & ((1 << HASH_BITS) - 1)
+    };
+    let insert = |head: &mut [usize], prev: &mut [usize], pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(pos);
+            prev[pos] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let limit = (data.len() - pos).min(MAX_MATCH);
+            let mut start = head[hash(pos)];
+            let mut chain = 0;
+            while start != usize::MAX && pos - start <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = (0..limit).take_while(|&i| data[start + i] == data[pos + i]).count();
+                if len > best.0 {
+                    best = (len, pos - start);
+                }
+                start = prev[start];
+                chain += 1;
+            }
+        }
+
+        if best.0 >= MIN_MATCH {
+            tokens.push(Token::Match { len: best.0, dist: best.1 });
+            for p in pos..pos + best.0 {
+                insert(&mut head, &mut prev, p);
+            }
+            pos += best.0;
+        } else {
+            tokens.push(Token::Literal(data[pos]));
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    tokens
+}
+
+// Fixed literal/length code (RFC 1951, 3.2.6)
+fn write_fixed_symbol(out: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => out.write_huffman(0x30 + symbol, 8),
+        144..=255 => out.write_huffman(0x190 + symbol - 144, 9),
+        256..=279 => out.write_huffman(symbol - 256, 7),
+        _ => out.write_huffman(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn read_fixed_symbol(input: &mut BitReader) -> Result<u16, DeflateError> {
+    let mut code = input.read_huffman(7)?;
+    if code <= 0x17 {
+        return Ok(code as u16 + 256);
+    }
+    code = (code << 1) | input.read_bits(1)?;
+    match code {
+        0x30..=0xbf => return Ok((code - 0x30) as u16),
+        0xc0..=0xc7 => return Ok((code - 0xc0) as u16 + 280),
+        _ => {}
+    }
+    code = (code << 1) | input.read_bits(1)?;
+    match code {
+        0x190..=0x1ff => Ok((code - 0x190) as u16 + 144),
+        _ => Err(DeflateError::InvalidSymbol(code as u16)),
+    }
+}
+
+// DEFLATE packs bits LSB first; Huffman codes go in MSB first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    used: u8,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, count: u8) {
+        for i in 0..count {
+            self.acc |= ((value >> i) & 1) << self.used;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.acc as u8);
+                self.acc = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    fn write_huffman(&mut self, code: u32, len: u8) {
+        for i in (0..len).rev() {
+            self.write_bits((code >> i) & 1, 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize, // in bits
+}
+
+impl BitReader<'_> {
+    fn read_bits(&mut self, count: u8) -> Result<u32, DeflateError> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.pos / 8).ok_or(DeflateError::UnexpectedEof)?;
+            value |= (((byte >> (self.pos % 8)) & 1) as u32) << i;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_huffman(&mut self, len: u8) -> Result<u32, DeflateError> {
+        let mut code = 0;
+        for _ in 0..len {
+            code = (code << 1) | self.read_bits(1)?;
+        }
+        Ok(code)
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+}
+
+/// Round trips, compression of repetitive input, and the fixed cost of a
+/// literal that the CRIME attack relies on.
+pub fn _test_deflate() -> Result<(), Box<dyn Error>> {
+    let samples: [&[u8]; 4] = [
+        b"",
+        b"abcabcabcabcabcabc",
+        b"Cookie: sessionid=TmV2ZXIgcmV2ZWFsIHRoZSBXdS1UYW5nIFNlY3JldCE=",
+        &[0xffu8; 1000],
+    ];
+    for sample in samples {
+        let compressed = deflate(sample);
+        println!(
+            "{:>4} bytes -> {:>3} bytes, round trip ok: {}",
+            sample.len(), compressed.len(), inflate(&compressed)? == sample,
+        );
+    }
+
+    let stored = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+    println!("Stored block: {:?}", String::from_utf8_lossy(&inflate(&stored)?));
+
+    // 8 literals of 0x80..0x88 add exactly 64 bits, i.e. 8 bytes
+    let base = deflate(b"sessionid=abc").len();
+    let longer = deflate(&[&[0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87][..], b"sessionid=abc"].concat()).len();
+    println!("Eight 8-bit literals add {} bytes", longer - base);
+    Ok(())
+}
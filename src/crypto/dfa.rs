@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+use std::error::Error;
+use rand::{Rng, RngCore, rngs::OsRng};
+use crate::crypto::aes_constants::INV_SBOX;
+use crate::crypto::aes_encyption::{AesStandard, AesError, RoundStep, gen_key};
+use crate::crypto::secret::SecretKey;
+
+// MixColumns coefficients applied to a single-byte difference in row `r`:
+// FAULT_COEFFS[r][i] is the multiplier that lands in output row i
+const FAULT_COEFFS: [[u8; 4]; 4] = [
+    [2, 1, 1, 3],
+    [3, 2, 1, 1],
+    [1, 3, 2, 1],
+    [1, 1, 3, 2],
+];
+
+/// A single-byte fault: `mask` is XORed into state byte `position` just
+/// before the MixColumns of `round`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fault {
+    pub round: usize,
+    pub position: usize,
+    pub mask: u8,
+}
+
+/// Ciphertexts of the same plaintext without and with a fault.
+#[derive(Clone, Debug)]
+pub struct FaultyPair {
+    pub correct: [u8; 16],
+    pub faulty: [u8; 16],
+}
+
+/// Encrypt with a fault injected through the round hook.
+pub fn encrypt_with_fault(aes: &AesStandard, plaintext: &[u8; 16], fault: Fault) -> Result<[u8; 16], AesError> {
+    if fault.round == 0 || fault.round >= aes.rounds() {
+        return Err(AesError::InvalidLength("Fault round must have a MixColumns step"));
+    }
+    if fault.position >= 16 {
+        return Err(AesError::InvalidLength("Fault position must be a state byte (0..16)"));
+    }
+    Ok(aes.encrypt_block_hooked(plaintext, |round, step, state| {
+        if round == fault.round && step == RoundStep::ShiftRows {
+            state[fault.position] ^= fault.mask;
+        }
+    }))
+}
+
+/// Collect one correct/faulty pair per entry of `positions`, each with a random
+/// plaintext and a random non-zero fault before the MixColumns of round Nr - 1.
+pub fn collect_faulty_pairs(aes: &AesStandard, positions: &[usize]) -> Result<Vec<FaultyPair>, AesError> {
+    collect_faulty_pairs_with_rng(aes, positions, &mut OsRng)
+}
+
+/// `collect_faulty_pairs` with plaintexts and fault masks drawn from `rng`.
+pub fn collect_faulty_pairs_with_rng(
+    aes: &AesStandard,
+    positions: &[usize],
+    rng: &mut impl RngCore,
+) -> Result<Vec<FaultyPair>, AesError> {
+    positions
+        .iter()
+        .map(|&position| {
+            let plaintext: [u8; 16] = rng.r#gen();
+            let fault = Fault { round: aes.rounds() - 1, position, mask: rng.gen_range(1..=255) };
+            Ok(FaultyPair {
+                correct: aes.encrypt_block(&plaintext),
+                faulty: encrypt_with_fault(aes, &plaintext, fault)?,
+            })
+        })
+        .collect()
+}
+
+// Ciphertext positions reached by a fault in state column `column`: row i of
+// that column ends up in column (column - i) mod 4 after the last ShiftRows.
+fn column_positions(column: usize) -> [usize; 4] {
+    let mut positions = [0usize; 4];
+    for (row, pos) in positions.iter_mut().enumerate() {
+        *pos = 4 * ((column + 4 - row) % 4) + row;
+    }
+    positions
+}
+
+// Which state column a pair was faulted in, from the bytes that differ
+fn faulted_column(pair: &FaultyPair) -> Option<usize> {
+    let differing: Vec<usize> = (0..16).filter(|&i| pair.correct[i] != pair.faulty[i]).collect();
+    (0..4).find(|&column| {
+        let mut positions = column_positions(column).to_vec();
+        positions.sort_unstable();
+        positions == differing
+    })
+}
+
+// Last round key candidates (4 bytes, in column_positions order) for one pair.
+//
+// For the right key bytes, undoing the last round gives the difference after
+// round Nr - 1 MixColumns: coefficient * e for some fault value e. The fault
+// row is not assumed known, so all four coefficient patterns are tried.
+fn column_candidates(pair: &FaultyPair, column: usize) -> HashSet<[u8; 4]> {
+    let positions = column_positions(column);
+
+    // keys_by_delta[i][d]: key bytes at row i giving input difference d
+    let mut keys_by_delta: Vec<Vec<Vec<u8>>> = vec![vec![Vec::new(); 256]; 4];
+    for (row, &pos) in positions.iter().enumerate() {
+        let (c, f) = (pair.correct[pos], pair.faulty[pos]);
+        for k in 0..=255u8 {
+            let delta = INV_SBOX[(c ^ k) as usize] ^ INV_SBOX[(f ^ k) as usize];
+            keys_by_delta[row][delta as usize].push(k);
+        }
+    }
+
+    let mut candidates = HashSet::new();
+    for coeffs in FAULT_COEFFS {
+        for e in 1..=255u8 {
+            let lists: Vec<&Vec<u8>> = (0..4)
+                .map(|row| &keys_by_delta[row][AesStandard::gmul(coeffs[row], e) as usize])
+                .collect();
+            for &k0 in lists[0] {
+                for &k1 in lists[1] {
+                    for &k2 in lists[2] {
+                        for &k3 in lists[3] {
+                            candidates.insert([k0, k1, k2, k3]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// Piret–Quisquater attack: recover the last round key of AES-128 from
+/// single-byte faults injected before the round 9 MixColumns.
+///
+/// Each pair constrains the four key bytes its fault spreads to. The fault row
+/// is not assumed known, so two pairs per column usually leave one candidate
+/// and a third settles the rest. Pairs whose difference pattern is not a
+/// single column are ignored. Returns `None` until every column is pinned down.
+pub fn piret_quisquater(pairs: &[FaultyPair]) -> Option<[u8; 16]> {
+    let mut columns: [Option<HashSet<[u8; 4]>>; 4] = [None, None, None, None];
+    for pair in pairs {
+        let Some(column) = faulted_column(pair) else {
+            continue;
+        };
+        let candidates = column_candidates(pair, column);
+        columns[column] = Some(match columns[column].take() {
+            Some(known) => known.intersection(&candidates).copied().collect(),
+            None => candidates,
+        });
+    }
+
+    let mut round_key = [0u8; 16];
+    for (column, candidates) in columns.iter().enumerate() {
+        let candidates = candidates.as_ref()?;
+        if candidates.len() != 1 {
+            return None;
+        }
+        let key_bytes = candidates.iter().next()?;
+        for (&pos, &k) in column_positions(column).iter().zip(key_bytes.iter()) {
+            round_key[pos] = k;
+        }
+    }
+    Some(round_key)
+}
+
+/// Full DFA: last round key from the faulty pairs, then the cipher key via the
+/// inverse key schedule.
+///
+/// `key_len` and `rounds` describe the attacked cipher. Only AES-128 is
+/// supported (any round count, so reduced-round variants work too): one
+/// round key pins down its whole schedule, while AES-192/256 would need two.
+/// Returns `None` for other key sizes rather than a wrong key.
+pub fn dfa_attack(pairs: &[FaultyPair], key_len: usize, rounds: usize) -> Option<SecretKey> {
+    if key_len != 16 || !(2..=10).contains(&rounds) {
+        return None;
+    }
+    let round_key = piret_quisquater(pairs)?;
+    let (cipher_key, _) = AesStandard::invert_key_schedule(&round_key, rounds, key_len).ok()?;
+    Some(cipher_key)
+}
+
+pub fn _test_dfa() -> Result<(), Box<dyn Error>> {
+    let key = gen_key(16);
+    let aes = AesStandard::new(&key)?;
+
+    // three faults per state column
+    let positions = [0, 1, 2, 5, 6, 7, 10, 11, 8, 15, 12, 13];
+    let pairs = collect_faulty_pairs(&aes, &positions)?;
+    println!("Collected {} correct/faulty ciphertext pairs", pairs.len());
+
+    match dfa_attack(&pairs, 16, aes.rounds()) {
+        Some(recovered) => println!(
+            "Recovered key {} ({})",
+            hex::encode(&recovered),
+            if recovered[..] == key[..] { "correct" } else { "wrong" },
+        ),
+        None => println!("DFA failed to isolate the last round key!"),
+    }
+
+    // reduced-round AES-128 shares the schedule, AES-256 needs two round keys
+    let reduced = AesStandard::with_rounds(&key, 7)?;
+    let pairs = collect_faulty_pairs(&reduced, &positions)?;
+    let recovered = dfa_attack(&pairs, 16, reduced.rounds());
+    println!("7-round AES-128 key recovered: {}", recovered.is_some_and(|k| k[..] == key[..]));
+
+    let aes256 = AesStandard::new(&gen_key(32))?;
+    let pairs = collect_faulty_pairs(&aes256, &positions)?;
+    println!("AES-256 refused: {}", dfa_attack(&pairs, 32, aes256.rounds()).is_none());
+    Ok(())
+}
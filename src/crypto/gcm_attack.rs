@@ -0,0 +1,128 @@
+use std::error::Error;
+use rand::{rngs::OsRng, RngCore};
+use crate::crypto::aes_encyption::{AesStandard, gen_key, random_bytes, seeded_rng};
+use crate::crypto::aes_gcm::{ghash, ghash_blocks};
+use crate::crypto::gf128::Gf128;
+use crate::crypto::gf128_poly::{Gf128Poly, roots_with_rng};
+
+/// One observed AES-GCM message: associated data, ciphertext and tag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GcmMessage {
+    pub aad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: [u8; 16],
+}
+
+/// Tag polynomial of a message: sum B_i * X^(m-i+1) + T.
+/// Evaluated at the real hash key H it equals the tag mask E_K(J0).
+pub fn tag_polynomial(msg: &GcmMessage) -> Gf128Poly {
+    let blocks = ghash_blocks(&msg.aad, &msg.ciphertext);
+    let m = blocks.len();
+
+    // coefficient of X^k is B_(m-k+1); the constant term is the tag
+    let mut coeffs = vec![Gf128::ZERO; m + 1];
+    coeffs[0] = Gf128::from_block(&msg.tag);
+    for (i, block) in blocks.into_iter().enumerate() {
+        coeffs[m - i] = block;
+    }
+    Gf128Poly::new(coeffs)
+}
+
+/// Candidate hash keys H for two messages encrypted under the same key and nonce.
+///
+/// Both tag polynomials share the mask E_K(J0), so their difference vanishes at H.
+/// The difference is factored (square-free, distinct-degree, equal-degree) and
+/// the roots of its linear factors are returned.
+pub fn recover_auth_key_candidates(first: &GcmMessage, second: &GcmMessage) -> Vec<Gf128> {
+    recover_auth_key_candidates_with_rng(first, second, &mut OsRng)
+}
+
+/// `recover_auth_key_candidates` with the factoring randomness drawn from `rng`,
+/// which fixes the candidate order.
+pub fn recover_auth_key_candidates_with_rng(first: &GcmMessage, second: &GcmMessage, rng: &mut impl RngCore) -> Vec<Gf128> {
+    let diff = tag_polynomial(first).add(&tag_polynomial(second));
+    if diff.degree().unwrap_or(0) == 0 {
+        // identical messages (or a constant difference) give no information
+        return Vec::new();
+    }
+    roots_with_rng(&diff.monic(), rng)
+}
+
+/// Forge the tag for `(aad, ciphertext)` under the same key and nonce as `known`,
+/// assuming `h` is the correct hash key.
+pub fn forge_tag(h: Gf128, known: &GcmMessage, aad: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    let mask = Gf128::from_block(&known.tag) + ghash(h, &known.aad, &known.ciphertext);
+    (ghash(h, aad, ciphertext) + mask).to_block()
+}
+
+/// The "forbidden attack": recover H from a nonce-reuse pair and forge an
+/// attacker-chosen message. Each candidate H is tried against `verify`
+/// (a decryption oracle returning whether the tag was accepted); the first
+/// accepted key and forgery are returned.
+pub fn forbidden_attack(
+    first: &GcmMessage,
+    second: &GcmMessage,
+    forged_aad: &[u8],
+    forged_ciphertext: &[u8],
+    verify: impl Fn(&GcmMessage) -> bool,
+) -> Option<(Gf128, GcmMessage)> {
+    forbidden_attack_with_rng(first, second, forged_aad, forged_ciphertext, verify, &mut OsRng)
+}
+
+/// `forbidden_attack` with the factoring randomness drawn from `rng`, so a
+/// seeded run tries the candidates in the same order every time.
+pub fn forbidden_attack_with_rng(
+    first: &GcmMessage,
+    second: &GcmMessage,
+    forged_aad: &[u8],
+    forged_ciphertext: &[u8],
+    verify: impl Fn(&GcmMessage) -> bool,
+    rng: &mut impl RngCore,
+) -> Option<(Gf128, GcmMessage)> {
+    for h in recover_auth_key_candidates_with_rng(first, second, rng) {
+        let forged = GcmMessage {
+            aad: forged_aad.to_vec(),
+            ciphertext: forged_ciphertext.to_vec(),
+            tag: forge_tag(h, first, forged_aad, forged_ciphertext),
+        };
+        if verify(&forged) {
+            return Some((h, forged));
+        }
+    }
+    None
+}
+
+pub fn _test_gcm_forbidden_attack() -> Result<(), Box<dyn Error>> {
+    let aes = AesStandard::new(&gen_key(16))?;
+    let nonce = random_bytes(12);
+
+    let (ct1, tag1) = aes.encrypt_gcm(b"transfer 100 to alice, ref 0001", b"header v1", &nonce)?;
+    let (ct2, tag2) = aes.encrypt_gcm(b"transfer 250 to bob, ref 0002!!", b"header v1", &nonce)?;
+    let first = GcmMessage { aad: b"header v1".to_vec(), ciphertext: ct1, tag: tag1 };
+    let second = GcmMessage { aad: b"header v1".to_vec(), ciphertext: ct2, tag: tag2 };
+
+    let candidates = recover_auth_key_candidates(&first, &second);
+    println!("Candidate hash keys: {}", candidates.len());
+    if candidates.contains(&aes.gcm_hash_key()) {
+        println!("Real hash key is among the candidates");
+    } else {
+        println!("Hash key recovery failed!");
+    }
+
+    // flip bits of the known ciphertext: CTR mode makes this a plaintext edit
+    let mut forged_ct = first.ciphertext.clone();
+    forged_ct[9] ^= b'1' ^ b'9';
+    let verify = |msg: &GcmMessage| aes.decrypt_gcm(&msg.ciphertext, &msg.aad, &nonce, &msg.tag).is_ok();
+
+    match forbidden_attack(&first, &second, b"header v2", &forged_ct, verify) {
+        Some((_, forged)) => {
+            let pt = aes.decrypt_gcm(&forged.ciphertext, &forged.aad, &nonce, &forged.tag)?;
+            println!("Forged message accepted: {}", String::from_utf8_lossy(&pt));
+        }
+        None => println!("Forgery failed!"),
+    }
+
+    let replay = |seed| forbidden_attack_with_rng(&first, &second, b"header v2", &forged_ct, verify, &mut seeded_rng(seed));
+    println!("Seeded runs replay identically: {}", replay(46) == replay(46));
+    Ok(())
+}
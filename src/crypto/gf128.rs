@@ -0,0 +1,136 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+use rand::RngCore;
+
+/// Element of GF(2^128) in the GCM bit order (NIST SP 800-38D).
+///
+/// A 16-byte block is read big-endian into a `u128`; the most significant bit
+/// holds the coefficient of x^0 and the least significant bit that of x^127.
+/// Reduction is modulo x^128 + x^7 + x^2 + x + 1.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gf128(pub u128);
+
+impl Gf128 {
+    pub const ZERO: Gf128 = Gf128(0);
+    pub const ONE: Gf128 = Gf128(1u128 << 127);
+
+    /// The polynomial `x` (the generator used as the GHASH variable).
+    pub const X: Gf128 = Gf128(1u128 << 126);
+
+    // x^128 = x^7 + x^2 + x + 1, written in the reflected bit order
+    const R: u128 = 0xe1u128 << 120;
+
+    pub fn from_block(block: &[u8; 16]) -> Self {
+        Gf128(u128::from_be_bytes(*block))
+    }
+
+    /// Reads up to 16 bytes, zero-padding on the right (partial GHASH blocks).
+    pub fn from_partial(bytes: &[u8]) -> Self {
+        let mut block = [0u8; 16];
+        block[..bytes.len()].copy_from_slice(bytes);
+        Self::from_block(&block)
+    }
+
+    pub fn to_block(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn random(rng: &mut impl RngCore) -> Self {
+        let mut block = [0u8; 16];
+        rng.fill_bytes(&mut block);
+        Self::from_block(&block)
+    }
+
+    // ----------------------------
+    // Field arithmetic
+    // ----------------------------
+    // Algorithm 1 of SP 800-38D: shift-and-add over the bits of `self`
+    fn gf_mul(self, other: Gf128) -> Gf128 {
+        let mut z: u128 = 0;
+        let mut v: u128 = other.0;
+        for i in (0..128).rev() {
+            if (self.0 >> i) & 1 == 1 {
+                z ^= v;
+            }
+            v = if v & 1 == 1 { (v >> 1) ^ Self::R } else { v >> 1 };
+        }
+        Gf128(z)
+    }
+
+    pub fn square(self) -> Gf128 {
+        self.gf_mul(self)
+    }
+
+    /// Raises `self` to an arbitrary 128-bit exponent (square-and-multiply).
+    pub fn pow(self, mut exp: u128) -> Gf128 {
+        let mut base = self;
+        let mut result = Gf128::ONE;
+        while exp != 0 {
+            if exp & 1 == 1 {
+                result *= base;
+            }
+            base = base.square();
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Multiplicative inverse via Fermat: a^(2^128 - 2). Zero maps to zero.
+    pub fn inverse(self) -> Gf128 {
+        self.pow(u128::MAX - 1)
+    }
+
+    /// Square root, a^(2^127). Squaring is a bijection in characteristic 2.
+    pub fn sqrt(self) -> Gf128 {
+        let mut r = self;
+        for _ in 0..127 {
+            r = r.square();
+        }
+        r
+    }
+}
+
+// Addition in characteristic 2 is XOR
+impl Add for Gf128 {
+    type Output = Gf128;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, other: Gf128) -> Gf128 {
+        Gf128(self.0 ^ other.0)
+    }
+}
+
+impl AddAssign for Gf128 {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn add_assign(&mut self, other: Gf128) {
+        self.0 ^= other.0;
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+    fn mul(self, other: Gf128) -> Gf128 {
+        self.gf_mul(other)
+    }
+}
+
+impl MulAssign for Gf128 {
+    fn mul_assign(&mut self, other: Gf128) {
+        *self = self.gf_mul(other);
+    }
+}
+
+impl fmt::Debug for Gf128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gf128({:032x})", self.0)
+    }
+}
+
+impl fmt::Display for Gf128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
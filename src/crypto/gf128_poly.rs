@@ -0,0 +1,368 @@
+use std::fmt;
+use rand::{rngs::OsRng, RngCore};
+use crate::crypto::gf128::Gf128;
+
+/// Polynomial over GF(2^128), coefficients stored lowest degree first.
+/// Always kept normalised: no trailing zero coefficients, so the zero
+/// polynomial is an empty vector.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Gf128Poly {
+    coeffs: Vec<Gf128>,
+}
+
+impl Gf128Poly {
+    pub fn new(mut coeffs: Vec<Gf128>) -> Self {
+        while coeffs.last().is_some_and(|c| c.is_zero()) {
+            coeffs.pop();
+        }
+        Self { coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Self { coeffs: Vec::new() }
+    }
+
+    pub fn one() -> Self {
+        Self { coeffs: vec![Gf128::ONE] }
+    }
+
+    /// The polynomial `x`.
+    pub fn x() -> Self {
+        Self { coeffs: vec![Gf128::ZERO, Gf128::ONE] }
+    }
+
+    /// Single term `c * x^degree`.
+    pub fn monomial(c: Gf128, degree: usize) -> Self {
+        let mut coeffs = vec![Gf128::ZERO; degree + 1];
+        coeffs[degree] = c;
+        Self::new(coeffs)
+    }
+
+    pub fn coeffs(&self) -> &[Gf128] {
+        &self.coeffs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.coeffs.len() == 1 && self.coeffs[0] == Gf128::ONE
+    }
+
+    /// Degree of the polynomial, `None` for zero.
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn leading(&self) -> Gf128 {
+        self.coeffs.last().copied().unwrap_or(Gf128::ZERO)
+    }
+
+    /// Horner evaluation at `x`.
+    pub fn eval(&self, x: Gf128) -> Gf128 {
+        self.coeffs.iter().rev().fold(Gf128::ZERO, |acc, &c| acc * x + c)
+    }
+
+    /// Divide through by the leading coefficient.
+    pub fn monic(&self) -> Self {
+        if self.is_zero() {
+            return Self::zero();
+        }
+        let inv = self.leading().inverse();
+        self.scale(inv)
+    }
+
+    pub fn scale(&self, c: Gf128) -> Self {
+        Self::new(self.coeffs.iter().map(|&a| a * c).collect())
+    }
+
+    // ----------------------------
+    // Ring operations
+    // ----------------------------
+    pub fn add(&self, other: &Self) -> Self {
+        let len = self.coeffs.len().max(other.coeffs.len());
+        let mut out = vec![Gf128::ZERO; len];
+        for (i, &c) in self.coeffs.iter().enumerate() {
+            out[i] += c;
+        }
+        for (i, &c) in other.coeffs.iter().enumerate() {
+            out[i] += c;
+        }
+        Self::new(out)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+        let mut out = vec![Gf128::ZERO; self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                out[i + j] += a * b;
+            }
+        }
+        Self::new(out)
+    }
+
+    /// Long division, returns `(quotient, remainder)`. Panics on a zero divisor.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let d_deg = divisor.degree().expect("Polynomial division by zero");
+        let lead_inv = divisor.leading().inverse();
+
+        let mut rem = self.coeffs.clone();
+        if rem.len() <= d_deg {
+            return (Self::zero(), self.clone());
+        }
+        let mut quot = vec![Gf128::ZERO; rem.len() - d_deg];
+
+        for i in (0..quot.len()).rev() {
+            let coef = rem[i + d_deg] * lead_inv;
+            if coef.is_zero() {
+                continue;
+            }
+            quot[i] = coef;
+            for (j, &d) in divisor.coeffs.iter().enumerate() {
+                rem[i + j] += coef * d;
+            }
+        }
+        rem.truncate(d_deg);
+        (Self::new(quot), Self::new(rem))
+    }
+
+    pub fn rem(&self, modulus: &Self) -> Self {
+        self.div_rem(modulus).1
+    }
+
+    /// Monic greatest common divisor (Euclid).
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let r = a.rem(&b);
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+
+    /// Formal derivative. In characteristic 2 only odd-degree terms survive.
+    pub fn derivative(&self) -> Self {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { Gf128::ZERO })
+            .collect();
+        Self::new(coeffs)
+    }
+
+    /// `self^2 mod modulus`.
+    pub fn square_mod(&self, modulus: &Self) -> Self {
+        self.mul(self).rem(modulus)
+    }
+
+    /// `self^exp mod modulus` for a small exponent.
+    pub fn pow_mod(&self, mut exp: u64, modulus: &Self) -> Self {
+        let mut base = self.rem(modulus);
+        let mut result = Self::one().rem(modulus);
+        while exp != 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base).rem(modulus);
+            }
+            base = base.square_mod(modulus);
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// `self^(q^k) mod modulus` with q = 2^128, i.e. 128*k repeated squarings.
+    pub fn frobenius_mod(&self, k: usize, modulus: &Self) -> Self {
+        let mut r = self.rem(modulus);
+        for _ in 0..128 * k {
+            r = r.square_mod(modulus);
+        }
+        r
+    }
+
+    /// Square root of a polynomial whose derivative vanishes
+    /// (only even powers present): sqrt(sum a_2i x^2i) = sum sqrt(a_2i) x^i.
+    fn sqrt_even(&self) -> Self {
+        let coeffs = self.coeffs.iter().step_by(2).map(|c| c.sqrt()).collect();
+        Self::new(coeffs)
+    }
+}
+
+impl fmt::Debug for Gf128Poly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gf128Poly(deg={:?})", self.degree())
+    }
+}
+
+impl fmt::Display for Gf128Poly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let terms: Vec<String> = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(i, c)| match i {
+                0 => format!("{}", c),
+                1 => format!("{}*x", c),
+                _ => format!("{}*x^{}", c, i),
+            })
+            .collect();
+        write!(f, "{}", terms.join(" + "))
+    }
+}
+
+// ----------------------------
+// Factorisation over GF(2^128)[x]
+// ----------------------------
+
+/// Square-free factorisation of a monic polynomial (characteristic-2 variant
+/// of Yun's algorithm). Returns `(factor, multiplicity)` pairs where every
+/// factor is square-free and pairwise coprime.
+pub fn square_free_factorization(f: &Gf128Poly) -> Vec<(Gf128Poly, usize)> {
+    let f = f.monic();
+    let mut factors: Vec<(Gf128Poly, usize)> = Vec::new();
+    if f.degree().unwrap_or(0) == 0 {
+        return factors;
+    }
+
+    let deriv = f.derivative();
+    if deriv.is_zero() {
+        // f = g^2
+        for (g, m) in square_free_factorization(&f.sqrt_even()) {
+            factors.push((g, m * 2));
+        }
+        return factors;
+    }
+
+    let mut c = f.gcd(&deriv);
+    let mut w = f.div_rem(&c).0;
+    let mut i = 1;
+    while !w.is_one() {
+        let y = w.gcd(&c);
+        let fac = w.div_rem(&y).0;
+        if !fac.is_one() {
+            factors.push((fac.monic(), i));
+        }
+        i += 1;
+        w = y;
+        c = c.div_rem(&w).0;
+    }
+
+    // Whatever is left in `c` is a perfect square
+    if !c.is_one() {
+        for (g, m) in square_free_factorization(&c.sqrt_even()) {
+            factors.push((g, m * 2));
+        }
+    }
+    factors
+}
+
+/// Distinct-degree factorisation of a monic square-free polynomial.
+/// Returns `(g, d)` where `g` is the product of all irreducible factors of degree `d`.
+pub fn distinct_degree_factorization(f: &Gf128Poly) -> Vec<(Gf128Poly, usize)> {
+    let mut factors: Vec<(Gf128Poly, usize)> = Vec::new();
+    let mut rest = f.monic();
+    let x = Gf128Poly::x();
+    let mut h = x.clone();
+    let mut d = 1;
+
+    while rest.degree().unwrap_or(0) >= 2 * d {
+        // h = x^(q^d) mod rest
+        h = h.frobenius_mod(1, &rest);
+        let g = rest.gcd(&h.add(&x));
+        if !g.is_one() {
+            rest = rest.div_rem(&g).0;
+            h = h.rem(&rest);
+            factors.push((g, d));
+        }
+        d += 1;
+    }
+    if let Some(deg) = rest.degree().filter(|&deg| deg > 0) {
+        factors.push((rest, deg));
+    }
+    factors
+}
+
+/// Equal-degree factorisation (Cantor–Zassenhaus, characteristic 2).
+/// `f` must be monic, square-free and a product of irreducibles of degree `d`.
+///
+/// Odd characteristic uses a^((q^d - 1)/2); here we split with the absolute
+/// trace Tr(a) = a + a^2 + a^4 + ... + a^(2^(128d - 1)) mod f instead.
+pub fn equal_degree_factorization(f: &Gf128Poly, d: usize) -> Vec<Gf128Poly> {
+    equal_degree_factorization_with_rng(f, d, &mut OsRng)
+}
+
+/// `equal_degree_factorization` with the random splitting polynomials drawn
+/// from `rng`; a seeded RNG fixes the order of the factors.
+pub fn equal_degree_factorization_with_rng(f: &Gf128Poly, d: usize, rng: &mut impl RngCore) -> Vec<Gf128Poly> {
+    let n = f.degree().unwrap_or(0);
+    if n == 0 {
+        return Vec::new();
+    }
+    if n == d {
+        return vec![f.monic()];
+    }
+
+    loop {
+        let a = Gf128Poly::new((0..n).map(|_| Gf128::random(rng)).collect());
+        if a.degree().unwrap_or(0) == 0 {
+            continue;
+        }
+
+        let mut term = a.rem(f);
+        let mut trace = term.clone();
+        for _ in 1..128 * d {
+            term = term.square_mod(f);
+            trace = trace.add(&term);
+        }
+
+        let g = f.gcd(&trace);
+        let g_deg = g.degree().unwrap_or(0);
+        if g_deg > 0 && g_deg < n {
+            let mut out = equal_degree_factorization_with_rng(&g, d, rng);
+            out.extend(equal_degree_factorization_with_rng(&f.div_rem(&g).0, d, rng));
+            return out;
+        }
+    }
+}
+
+/// Full factorisation into monic irreducibles with multiplicities.
+pub fn factor(f: &Gf128Poly) -> Vec<(Gf128Poly, usize)> {
+    factor_with_rng(f, &mut OsRng)
+}
+
+pub fn factor_with_rng(f: &Gf128Poly, rng: &mut impl RngCore) -> Vec<(Gf128Poly, usize)> {
+    let mut out: Vec<(Gf128Poly, usize)> = Vec::new();
+    for (sf, mult) in square_free_factorization(f) {
+        for (g, d) in distinct_degree_factorization(&sf) {
+            for irreducible in equal_degree_factorization_with_rng(&g, d, rng) {
+                out.push((irreducible, mult));
+            }
+        }
+    }
+    out
+}
+
+/// All distinct roots in GF(2^128), taken from the linear factors of `f`.
+pub fn roots(f: &Gf128Poly) -> Vec<Gf128> {
+    roots_with_rng(f, &mut OsRng)
+}
+
+pub fn roots_with_rng(f: &Gf128Poly, rng: &mut impl RngCore) -> Vec<Gf128> {
+    factor_with_rng(f, rng)
+        .into_iter()
+        .filter(|(g, _)| g.degree() == Some(1))
+        // monic x + r has root r (addition is subtraction)
+        .map(|(g, _)| g.coeffs()[0])
+        .collect()
+}
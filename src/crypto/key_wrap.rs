@@ -0,0 +1,188 @@
+use std::error::Error;
+use zeroize::Zeroize;
+use crate::crypto::aes_encyption::{AesStandard, AesError};
+use crate::crypto::secret::{SecretBytes, SecretKey};
+use crate::basics::encodings::hex_to_bytes;
+
+/// Default initial value of RFC 3394, section 2.2.3.1.
+pub const KEY_WRAP_IV: [u8; 8] = [0xa6; 8];
+/// High half of the RFC 5649 alternative initial value; the low half is the
+/// message length indicator (MLI), the key length in bytes.
+pub const KEY_WRAP_PAD_IV: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+impl AesStandard {
+    /// AES key wrap (RFC 3394) with this context as the key-encryption key.
+    /// The key data must be a multiple of 8 bytes and at least 16 bytes.
+    pub fn wrap_key(&self, key_data: &[u8]) -> Result<Vec<u8>, AesError> {
+        if !key_data.len().is_multiple_of(8) || key_data.len() < 16 {
+            return Err(AesError::InvalidLength("Key data must be a multiple of 8 bytes, at least 16"));
+        }
+        Ok(self.wrap_with_iv(&KEY_WRAP_IV, key_data))
+    }
+
+    /// Inverse of `wrap_key`; fails with `IntegrityCheckFailed` when the
+    /// recovered initial value is not the RFC 3394 default.
+    pub fn unwrap_key(&self, wrapped: &[u8]) -> Result<SecretKey, AesError> {
+        if !wrapped.len().is_multiple_of(8) || wrapped.len() < 24 {
+            return Err(AesError::InvalidLength("Wrapped key must be a multiple of 8 bytes, at least 24"));
+        }
+        let (a, key_data) = self.unwrap_with_iv(wrapped);
+        // compare every byte so the check does not stop early
+        let diff = a.iter().zip(KEY_WRAP_IV.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+        if diff != 0 {
+            return Err(AesError::IntegrityCheckFailed);
+        }
+        Ok(SecretKey::from_slice(&key_data))
+    }
+
+    /// AES key wrap with padding (RFC 5649): any key length from 1 byte.
+    pub fn wrap_key_padded(&self, key_data: &[u8]) -> Result<Vec<u8>, AesError> {
+        if key_data.is_empty() || key_data.len() > u32::MAX as usize {
+            return Err(AesError::InvalidLength("Key data must be 1 to 2^32 - 1 bytes"));
+        }
+        let mut aiv = [0u8; 8];
+        aiv[..4].copy_from_slice(&KEY_WRAP_PAD_IV);
+        aiv[4..].copy_from_slice(&(key_data.len() as u32).to_be_bytes());
+
+        let mut padded = SecretBytes::zeroed(key_data.len().div_ceil(8) * 8);
+        padded[..key_data.len()].copy_from_slice(key_data);
+        if padded.len() == 8 {
+            // a single semiblock is encrypted directly as AIV || P
+            let mut block = [0u8; 16];
+            block[..8].copy_from_slice(&aiv);
+            block[8..].copy_from_slice(&padded);
+            let wrapped = self.encrypt_block(&block).to_vec();
+            block.zeroize();
+            return Ok(wrapped);
+        }
+        Ok(self.wrap_with_iv(&aiv, &padded))
+    }
+
+    /// Inverse of `wrap_key_padded`. The AIV prefix, the length indicator and
+    /// the zero padding are all checked; any mismatch is `IntegrityCheckFailed`.
+    pub fn unwrap_key_padded(&self, wrapped: &[u8]) -> Result<SecretKey, AesError> {
+        if !wrapped.len().is_multiple_of(8) || wrapped.len() < 16 {
+            return Err(AesError::InvalidLength("Wrapped key must be a multiple of 8 bytes, at least 16"));
+        }
+        let (a, padded) = if wrapped.len() == 16 {
+            let mut block = [0u8; 16];
+            block.copy_from_slice(wrapped);
+            let mut plain = self.decrypt_block(&block);
+            let mut a = [0u8; 8];
+            a.copy_from_slice(&plain[..8]);
+            let padded = SecretBytes::from_slice(&plain[8..]);
+            plain.zeroize();
+            (a, padded)
+        } else {
+            self.unwrap_with_iv(wrapped)
+        };
+
+        let mli = u32::from_be_bytes([a[4], a[5], a[6], a[7]]) as usize;
+        let prefix_ok = a[..4] == KEY_WRAP_PAD_IV;
+        let length_ok = mli <= padded.len() && mli + 8 > padded.len();
+        if !prefix_ok || !length_ok {
+            return Err(AesError::IntegrityCheckFailed);
+        }
+        if padded[mli..].iter().any(|&b| b != 0) {
+            return Err(AesError::IntegrityCheckFailed);
+        }
+        Ok(SecretKey::from_slice(&padded[..mli]))
+    }
+
+    // Wrapping function W (RFC 3394 2.2.1, index-based form): six passes over
+    // the semiblocks, each step encrypting A || R[i] and folding in t = n*j + i.
+    fn wrap_with_iv(&self, iv: &[u8; 8], key_data: &[u8]) -> Vec<u8> {
+        let n = key_data.len() / 8;
+        let mut a = u64::from_be_bytes(*iv);
+        let mut r: Vec<[u8; 8]> = key_data.chunks_exact(8).map(|c| c.try_into().unwrap()).collect();
+
+        for j in 0..6 {
+            for (i, semiblock) in r.iter_mut().enumerate() {
+                let mut block = [0u8; 16];
+                block[..8].copy_from_slice(&a.to_be_bytes());
+                block[8..].copy_from_slice(semiblock);
+                let b = self.encrypt_block(&block);
+                let t = (n * j + i + 1) as u64;
+                a = u64::from_be_bytes(b[..8].try_into().unwrap()) ^ t;
+                semiblock.copy_from_slice(&b[8..]);
+            }
+        }
+
+        let mut wrapped = a.to_be_bytes().to_vec();
+        wrapped.extend(r.iter().flatten());
+        wrapped
+    }
+
+    // Unwrapping function W^-1: returns the recovered A and the key data
+    fn unwrap_with_iv(&self, wrapped: &[u8]) -> ([u8; 8], SecretBytes) {
+        let n = wrapped.len() / 8 - 1;
+        let mut a = u64::from_be_bytes(wrapped[..8].try_into().unwrap());
+        let mut r: Vec<[u8; 8]> = wrapped[8..].chunks_exact(8).map(|c| c.try_into().unwrap()).collect();
+
+        for j in (0..6).rev() {
+            for (i, semiblock) in r.iter_mut().enumerate().rev() {
+                let t = (n * j + i + 1) as u64;
+                let mut block = [0u8; 16];
+                block[..8].copy_from_slice(&(a ^ t).to_be_bytes());
+                block[8..].copy_from_slice(semiblock);
+                let b = self.decrypt_block(&block);
+                a = u64::from_be_bytes(b[..8].try_into().unwrap());
+                semiblock.copy_from_slice(&b[8..]);
+            }
+        }
+        let key_data = SecretBytes::from(r.concat());
+        r.zeroize();
+        (a.to_be_bytes(), key_data)
+    }
+}
+
+/// RFC 3394 section 4 and RFC 5649 section 6 vectors, plus tamper detection.
+pub fn _test_key_wrap() -> Result<(), Box<dyn Error>> {
+    // (KEK, key data, wrapped)
+    let rfc3394 = [
+        (
+            "000102030405060708090a0b0c0d0e0f",
+            "00112233445566778899aabbccddeeff",
+            "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5",
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f",
+            "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21",
+        ),
+    ];
+    for (kek, key_data, expected) in rfc3394 {
+        let aes = AesStandard::new(&hex_to_bytes(kek)?)?;
+        let key_data = hex_to_bytes(key_data)?;
+        let wrapped = aes.wrap_key(&key_data)?;
+        let ok = wrapped == hex_to_bytes(expected)? && aes.unwrap_key(&wrapped)?[..] == key_data[..];
+        println!("RFC 3394 {}-bit KEK, {}-bit key: {}", kek.len() * 4, key_data.len() * 8,
+                 if ok { "matches" } else { "mismatch!" });
+    }
+
+    let rfc5649 = [
+        ("c37b7e6492584340bed12207808941155068f738", "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a"),
+        ("466f7250617369", "afbeb0f07dfbf5419200f2ccb50bb24f"),
+    ];
+    let aes = AesStandard::new(&hex_to_bytes("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8")?)?;
+    for (key_data, expected) in rfc5649 {
+        let key_data = hex_to_bytes(key_data)?;
+        let wrapped = aes.wrap_key_padded(&key_data)?;
+        let ok = wrapped == hex_to_bytes(expected)? && aes.unwrap_key_padded(&wrapped)?[..] == key_data[..];
+        println!("RFC 5649 {}-byte key: {}", key_data.len(), if ok { "matches" } else { "mismatch!" });
+    }
+
+    // flipping any bit of a wrapped key must be caught
+    let wrapped = aes.wrap_key(&[0x42; 32])?;
+    let caught = (0..wrapped.len() * 8).all(|bit| {
+        let mut tampered = wrapped.clone();
+        tampered[bit / 8] ^= 1 << (bit % 8);
+        matches!(aes.unwrap_key(&tampered), Err(AesError::IntegrityCheckFailed))
+    });
+    let padded = aes.wrap_key_padded(&[0x42; 5])?;
+    let mut tampered = padded.clone();
+    tampered[3] ^= 0x80;
+    let caught_padded = matches!(aes.unwrap_key_padded(&tampered), Err(AesError::IntegrityCheckFailed));
+    println!("Tampered wrapped keys rejected: {}", if caught && caught_padded { "yes" } else { "NO!" });
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroize;
+
+/// Compare two byte strings without stopping at the first difference.
+/// The lengths are treated as public.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Secret buffer (expanded keys, round keys) that is zeroed when dropped.
+///
+/// `Debug` shows only the length, and `==` compares in constant time. It
+/// derefs to a byte slice, so it can be read and written like a `Vec<u8>`
+/// but never grows: a reallocation would leave the old copy behind.
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// `len` zero bytes.
+    pub fn zeroed(len: usize) -> Self {
+        Self(vec![0u8; len])
+    }
+
+    /// Copies `bytes` in; the caller still owns (and should wipe) the original.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes(<redacted, {} bytes>)", self.0.len())
+    }
+}
+
+impl Zeroize for SecretBytes {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A cipher key: read-only `SecretBytes`, as returned by `gen_key`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey(SecretBytes);
+
+impl SecretKey {
+    /// `len` bytes from the OS RNG.
+    pub fn random(len: usize) -> Self {
+        Self::from_rng(&mut OsRng, len)
+    }
+
+    /// `len` bytes from `rng`; reproducible when `rng` is seeded.
+    pub fn from_rng(rng: &mut impl RngCore, len: usize) -> Self {
+        let mut key = SecretBytes::zeroed(len);
+        rng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        Self(SecretBytes::from_slice(bytes))
+    }
+}
+
+impl From<Vec<u8>> for SecretKey {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(SecretBytes::from(bytes))
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(<redacted, {} bytes>)", self.0.len())
+    }
+}
+
+/// Redaction, equality and wiping of the secret types.
+pub fn _test_secret() -> Result<(), Box<dyn Error>> {
+    let key = SecretKey::random(16);
+    println!("{:?} / {:?}", key, SecretBytes::zeroed(176));
+
+    let copy = SecretKey::from_slice(&key);
+    let mut other = copy.to_vec();
+    other[15] ^= 1;
+    println!(
+        "Equal to its copy: {}, equal after a one-bit change: {}, equal to a prefix: {}",
+        key == copy,
+        key == SecretKey::from(other),
+        ct_eq(&key, &key[..8]),
+    );
+
+    let mut buffer = SecretBytes::from_slice(b"round keys");
+    buffer.zeroize();
+    println!("Zeroized buffer is all zero: {}", buffer.iter().all(|&b| b == 0));
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use rand::{Rng, RngCore, rngs::OsRng};
+use crate::crypto::aes_constants::INV_SBOX;
+use crate::crypto::aes_encyption::{AesStandard, gen_key};
+use crate::crypto::secret::SecretKey;
+
+/// Λ-sets to try before giving up on narrowing every key byte to one candidate.
+const MAX_LAMBDA_SETS: usize = 16;
+
+/// Encrypt a Λ-set: 256 plaintexts equal everywhere except byte 0, which
+/// takes every value once.
+pub fn lambda_set(oracle: &impl Fn(&[u8; 16]) -> [u8; 16], constant: &[u8; 16]) -> Vec<[u8; 16]> {
+    (0..=255u8)
+        .map(|v| {
+            let mut pt = *constant;
+            pt[0] = v;
+            oracle(&pt)
+        })
+        .collect()
+}
+
+/// Recover the last round key of 4-round AES-128 (final round without MixColumns).
+///
+/// After three rounds every state byte of a Λ-set is balanced (XORs to zero).
+/// For each ciphertext byte and key guess k, undo the last round byte-wise with
+/// INV_SBOX[c ^ k]; only the correct k (and rare false positives) keeps the sum
+/// at zero. Further Λ-sets are intersected until each byte has one candidate.
+pub fn recover_last_round_key(oracle: impl Fn(&[u8; 16]) -> [u8; 16]) -> Option<[u8; 16]> {
+    recover_last_round_key_with_rng(oracle, &mut OsRng)
+}
+
+/// `recover_last_round_key` with the Λ-set constants drawn from `rng`.
+pub fn recover_last_round_key_with_rng(oracle: impl Fn(&[u8; 16]) -> [u8; 16], rng: &mut impl RngCore) -> Option<[u8; 16]> {
+    let mut candidates: Vec<Vec<u8>> = vec![(0..=255u8).collect(); 16];
+
+    for _ in 0..MAX_LAMBDA_SETS {
+        let constant: [u8; 16] = rng.r#gen();
+        let ciphertexts = lambda_set(&oracle, &constant);
+
+        for (pos, byte_candidates) in candidates.iter_mut().enumerate() {
+            byte_candidates.retain(|&k| {
+                ciphertexts.iter().fold(0u8, |acc, ct| acc ^ INV_SBOX[(ct[pos] ^ k) as usize]) == 0
+            });
+        }
+
+        if candidates.iter().any(|c| c.is_empty()) {
+            return None;
+        }
+        if candidates.iter().all(|c| c.len() == 1) {
+            let mut key = [0u8; 16];
+            for (k, c) in key.iter_mut().zip(candidates.iter()) {
+                *k = c[0];
+            }
+            return Some(key);
+        }
+    }
+    None
+}
+
+/// Full square attack: last round key of 4-round AES-128, then the key
+/// schedule run backwards to the master key.
+pub fn square_attack(oracle: impl Fn(&[u8; 16]) -> [u8; 16]) -> Option<SecretKey> {
+    square_attack_with_rng(oracle, &mut OsRng)
+}
+
+pub fn square_attack_with_rng(oracle: impl Fn(&[u8; 16]) -> [u8; 16], rng: &mut impl RngCore) -> Option<SecretKey> {
+    let round_key = recover_last_round_key_with_rng(oracle, rng)?;
+    let (cipher_key, _) = AesStandard::invert_key_schedule(&round_key, 4, 16).ok()?;
+    Some(cipher_key)
+}
+
+pub fn _test_square_attack() -> Result<(), Box<dyn Error>> {
+    let key = gen_key(16);
+    let aes = AesStandard::with_rounds(&key, 4)?;
+    println!("Attacking AES-128 reduced to {} rounds", aes.rounds());
+
+    match square_attack(|pt| aes.encrypt_block(pt)) {
+        Some(recovered) => println!(
+            "Recovered key {} ({})",
+            hex::encode(&recovered),
+            if recovered[..] == key[..] { "correct" } else { "wrong" },
+        ),
+        None => println!("Square attack failed!"),
+    }
+    Ok(())
+}
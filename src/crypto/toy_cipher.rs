@@ -0,0 +1,62 @@
+use crate::crypto::aes_encyption::AesError;
+use crate::crypto::block_cipher::BlockCipher;
+
+const TOY_ROUNDS: usize = 8;
+
+/// Toy 64-bit Feistel cipher with a 64-bit key, for exercising the generic
+/// modes and attacks on a block size other than 16. Not secure in any way.
+///
+/// Each round maps (L, R) to (R, L ^ F(R, k_i)) with
+/// F(x, k) = rotl((x ^ k) * 0x9e3779b9, 7), and the round keys rotate the key.
+pub struct ToyFeistel {
+    round_keys: [u32; TOY_ROUNDS],
+}
+
+impl ToyFeistel {
+    pub fn new(key: &[u8]) -> Result<Self, AesError> {
+        let key: [u8; 8] = key
+            .try_into()
+            .map_err(|_| AesError::InvalidLength("Toy cipher key must be 8 bytes"))?;
+        let key = u64::from_be_bytes(key);
+        let mut round_keys = [0u32; TOY_ROUNDS];
+        for (i, rk) in round_keys.iter_mut().enumerate() {
+            *rk = (key.rotate_left(8 * i as u32) >> 32) as u32 ^ i as u32;
+        }
+        Ok(Self { round_keys })
+    }
+
+    fn round(x: u32, k: u32) -> u32 {
+        (x ^ k).wrapping_mul(0x9e37_79b9).rotate_left(7)
+    }
+
+    fn halves(block: &[u8]) -> (u32, u32) {
+        let value = u64::from_be_bytes(block.try_into().expect("Toy cipher block must be 8 bytes"));
+        ((value >> 32) as u32, value as u32)
+    }
+
+    fn join(block: &mut [u8], left: u32, right: u32) {
+        block.copy_from_slice(&(((left as u64) << 32) | right as u64).to_be_bytes());
+    }
+}
+
+impl BlockCipher for ToyFeistel {
+    fn block_size(&self) -> usize {
+        8
+    }
+
+    fn encrypt_block_in_place(&self, block: &mut [u8]) {
+        let (mut left, mut right) = Self::halves(block);
+        for &k in &self.round_keys {
+            (left, right) = (right, left ^ Self::round(right, k));
+        }
+        Self::join(block, left, right);
+    }
+
+    fn decrypt_block_in_place(&self, block: &mut [u8]) {
+        let (mut left, mut right) = Self::halves(block);
+        for &k in self.round_keys.iter().rev() {
+            (left, right) = (right ^ Self::round(left, k), left);
+        }
+        Self::join(block, left, right);
+    }
+}
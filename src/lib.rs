@@ -12,9 +12,35 @@ pub mod basics {
 
 }
 pub mod crypto{
+    pub mod secret;
     pub mod aes_constants;
+    pub mod aes_ttable;
+    pub mod aes_bitsliced;
+    pub mod aes_ni;
     pub mod aes_encyption;
     pub mod aes_oracle;
+    pub mod cookie;
+    pub mod cut_and_paste;
+    pub mod deflate;
+    pub mod crime;
+    pub mod block_cipher;
+    pub mod padding;
+    pub mod toy_cipher;
+    pub mod des;
+    pub mod aes_modes;
+    pub mod aes_xts;
+    pub mod key_wrap;
+    pub mod aes_gcm;
+    pub mod aes_siv;
+    pub mod aes_ccm;
+    pub mod gf128;
+    pub mod gf128_poly;
+    pub mod gcm_attack;
+    pub mod cbc_mac;
+    pub mod dudect;
+    pub mod rijndael;
+    pub mod square_attack;
+    pub mod dfa;
 }
 pub mod utils;
 
@@ -37,9 +63,59 @@ pub use basics::repeating_key::{
 };
 
 pub use crypto::aes_encyption::{
-    AesStandard, AesError, pkcs7_padding, pkcs7_unpadding, gen_key
+    AesStandard, AesBackend, AesError, RoundStep, AesTrace, TraceEntry, PaddingError,
+    pkcs7_padding, pkcs7_unpadding, pkcs7_unpadding_ct,
+    gen_key, gen_key_with_rng, random_bytes, random_bytes_with_rng, seeded_rng,
 };
+pub use crypto::secret::{SecretBytes, SecretKey, ct_eq};
 pub use crypto::aes_oracle::{
     encryption_oracle_random, detect_mode, find_block_size,
-    find_next_byte, confirm_ecb, encryption_oracle, find_prefix_len
+    find_next_byte, confirm_ecb, find_prefix_len, OracleContext,
+    CipherMode, ModeDetection, detect_mode_scored, min_chosen_plaintext_len,
+    encryption_oracle_random_with, encryption_oracle_random_with_rng, detect_mode_with_block_size, ecb_suffix_oracle,
+};
+pub use crypto::cookie::{Cookie, CookieCodec, CookieFormat, CookieError, Escaping, DuplicatePolicy};
+pub use crypto::cut_and_paste::{CutAndPaste, ForgePlan, ForgeError};
+pub use crypto::deflate::{deflate, inflate, DeflateError};
+pub use crypto::crime::{CompressionOracle, CrimeMode, CrimeResult, crime_attack, recover_session_id};
+pub use crypto::block_cipher::{
+    BlockCipher, ecb_encrypt, ecb_decrypt, cbc_encrypt, cbc_decrypt,
+    ctr_apply, ofb_apply, cfb_encrypt, cfb_decrypt, cfb8_encrypt, cfb8_decrypt,
+    pcbc_encrypt, pcbc_decrypt,
+};
+pub use crypto::padding::{
+    Padding, Pkcs7, AnsiX923, Iso10126, Iso7816, ZeroPadding, PaddedEcb, PaddedCbc,
+    cbc_padding_oracle_attack,
+};
+pub use crypto::toy_cipher::ToyFeistel;
+pub use crypto::des::{
+    Des, TripleDes, WEAK_KEYS, SEMI_WEAK_KEY_PAIRS, is_weak_key, is_semi_weak_key,
+    BirthdayStats, sweet32_collisions, sweet32_collisions_with_rng,
+};
+pub use crypto::aes_modes::CtsVariant;
+pub use crypto::aes_xts::AesXts;
+pub use crypto::key_wrap::{KEY_WRAP_IV, KEY_WRAP_PAD_IV};
+pub use crypto::aes_gcm::{ghash, ghash_blocks};
+pub use crypto::aes_siv::AesSiv;
+pub use crypto::gf128::Gf128;
+pub use crypto::gf128_poly::{
+    Gf128Poly, square_free_factorization, distinct_degree_factorization,
+    equal_degree_factorization, factor, roots,
+    equal_degree_factorization_with_rng, factor_with_rng, roots_with_rng,
+};
+pub use crypto::gcm_attack::{
+    GcmMessage, tag_polynomial, recover_auth_key_candidates, recover_auth_key_candidates_with_rng,
+    forge_tag, forbidden_attack, forbidden_attack_with_rng,
+};
+pub use crypto::cbc_mac::{
+    cbc_mac, cbc_mac_zero_iv, aes_cmac, cmac_subkeys,
+    forge_cbc_mac_iv, cbc_mac_length_extension, forge_cbc_mac_hash_collision,
+};
+pub use crypto::rijndael::Rijndael;
+pub use crypto::square_attack::{
+    lambda_set, recover_last_round_key, recover_last_round_key_with_rng, square_attack, square_attack_with_rng,
+};
+pub use crypto::dfa::{
+    Fault, FaultyPair, encrypt_with_fault, collect_faulty_pairs, collect_faulty_pairs_with_rng,
+    piret_quisquater, dfa_attack,
 };